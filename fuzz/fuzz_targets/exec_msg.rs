@@ -0,0 +1,12 @@
+#![no_main]
+
+use cosmwasm_std::{from_slice, to_vec};
+use counting_contract::msg::ExecMsg;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(msg) = from_slice::<ExecMsg>(data) {
+        let serialized = to_vec(&msg).unwrap();
+        assert_eq!(from_slice::<ExecMsg>(&serialized).unwrap(), msg);
+    }
+});
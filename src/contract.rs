@@ -1,43 +1,64 @@
-use cosmwasm_std::{Coin, DepsMut, MessageInfo, Response, StdResult};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, BankMsg, Coin, Decimal, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply, Response,
+                   StdResult, Storage, SubMsgResult, Timestamp, to_binary, WasmMsg};
+use cw_storage_plus::{Bound, Item};
 use crate::{ContractError, InstantiateMsg};
-use crate::state::{OWNER, PARENT_DONATION, ParentDonation, STATE, State};
+use crate::state::{CAMPAIGN_ESCROW, COUNTER_HISTORY, DEFAULT_COUNTER, DEFAULT_MAX_PARENT_DEPTH, FEE, FEES_ACCRUED,
+                   HISTORY_RETENTION, IBC_ESCROW, MATCHING_POOL, MAX_PARENT_DEPTH, OWNER, PARENT_DONATION, ParentDonation,
+                   PARENT_SHARE_ESCROW, PENDING_SWAPS, Proposal, SCHEDULED_MINIMAL_DONATION, STATE, State,
+                   SUBSCRIPTION_ESCROW, VESTING, WITHDRAWN};
+use crate::validation;
 use cw2::{CONTRACT, get_contract_version, set_contract_version};
-use crate::msg::{MigrateMsg, Parent};
+use crate::msg::{ExecMsg, MigrateMsg, Parent, ParentPayout, ParentResp, ProposalStatus, QueryMsg, VestingSchedule};
 use serde::{Serialize, Deserialize};
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// History entries pruned on every counter change
+const PRUNE_BATCH: u32 = 10;
+/// Versions of this contract from before the parent chains, which neither answer `QueryMsg::Parent`
+/// nor take the fields `ExecMsg::Donate` got since
+const LEGACY_VERSIONS: [&str; 3] = ["0.1.0", "0.2.0", "0.3.0"];
+/// Set on the subscription id to make the reply id of its instalment, swaps take their reply ids
+/// counting up from zero
+const INSTALMENT_REPLY_FLAG: u64 = 1 << 63;
 
 #[allow(dead_code)]
-pub fn instantiate(deps: DepsMut, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+pub fn instantiate(mut deps: DepsMut, env: Env, info: MessageInfo, msg: InstantiateMsg) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    STATE.save(
+    validation::minimal_donation(&msg.minimal_donation)?;
+
+    MAX_PARENT_DEPTH.save(deps.storage, &msg.max_parent_depth.unwrap_or(DEFAULT_MAX_PARENT_DEPTH))?;
+
+    if let Some(vesting) = msg.vesting {
+        validation::vesting(&vesting)?;
+        VESTING.save(deps.storage, &vesting)?;
+    }
+
+    if let Some(fee) = msg.fee {
+        let fee = validation::fee(deps.as_ref(), fee)?;
+        FEE.save(deps.storage, &fee)?;
+    }
+
+    save_counter(
         deps.storage,
+        &env,
+        DEFAULT_COUNTER,
         &State {
             counter: msg.counter,
             minimal_donation: msg.minimal_donation,
             donating_parent: msg.parent.as_ref().map(|p| p.donating_period),
+            owner: None,
         }
     )?;
 
-    if let Some(parent) = msg.parent {
-        PARENT_DONATION.save(
-            deps.storage,
-            &ParentDonation {
-                address: deps.api.addr_validate(&parent.addr)?,
-                donating_parent_period: parent.donating_period,
-                part: parent.part,
-            }
-        )?;
-    }
+    let register = save_parent(deps.branch(), &env, msg.parent)?;
 
     OWNER.save(deps.storage, &info.sender)?;
-    Ok(Response::new())
+    Ok(Response::new().add_messages(register))
 }
 
-pub fn migrate(mut deps: DepsMut, msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let contract = get_contract_version(deps.storage)?;
 
     if contract.contract != CONTRACT_NAME {
@@ -45,48 +66,47 @@ pub fn migrate(mut deps: DepsMut, msg: MigrateMsg) -> Result<Response, ContractE
     }
 
     let resp = match contract.version.as_str() {
-        "0.1.0" => migrate_0_1_0(deps.branch(), msg.parent)?,
-        "0.2.0" => migrate_0_2_0(deps.branch(), msg.parent)?,
-        version if version == CONTRACT_VERSION => return Ok(Response::new()),
+        "0.1.0" => migrate_0_1_0(deps.branch(), &env, msg.parent)?,
+        "0.2.0" => migrate_0_2_0(deps.branch(), &env, msg.parent)?,
+        "0.3.0" => migrate_0_3_0(deps.branch(), &env)?,
+        version if version == CONTRACT_VERSION => Response::new(),
         _ => return Err(ContractError::InvalidVersion(contract.version))
     };
 
+    if let Some(payout) = msg.parent_payout {
+        set_parent_payout(deps.storage, payout)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(resp)
 }
 
-pub fn migrate_0_1_0(deps: DepsMut, parent: Option<Parent>) -> Result<Response, ContractError> {
+pub fn migrate_0_1_0(mut deps: DepsMut, env: &Env, parent: Option<Parent>) -> Result<Response, ContractError> {
     const COUNTER: Item<u64> = Item::new("counter");
     const MINIMAL_DONATION: Item<Coin> = Item::new("minimal_donation");
 
     let counter = COUNTER.load(deps.storage)?;
     let minimal_donation = MINIMAL_DONATION.load(deps.storage)?;
 
-    STATE.save(
+    save_counter(
         deps.storage,
+        env,
+        DEFAULT_COUNTER,
         &State {
             counter,
             minimal_donation,
             donating_parent: parent.as_ref().map(|p| p.donating_period),
+            owner: None,
         },
     )?;
 
-    if let Some(parent) = parent {
-        PARENT_DONATION.save(
-            deps.storage,
-            &ParentDonation {
-                address: deps.api.addr_validate(&parent.addr)?,
-                donating_parent_period: parent.donating_period,
-                part: parent.part,
-            }
-        )?;
-    }
+    let register = save_parent(deps.branch(), env, parent)?;
 
-    Ok(Response::new())
+    Ok(Response::new().add_messages(register))
 }
 
-pub fn migrate_0_2_0(deps: DepsMut, parent: Option<Parent>) -> Result<Response, ContractError> {
+pub fn migrate_0_2_0(mut deps: DepsMut, env: &Env, parent: Option<Parent>) -> Result<Response, ContractError> {
     #[derive(Deserialize, Serialize)]
     struct OldState {
         counter: u64,
@@ -97,123 +117,2549 @@ pub fn migrate_0_2_0(deps: DepsMut, parent: Option<Parent>) -> Result<Response,
 
     let state = OLD_STATE.load(deps.storage)?;
 
-    STATE.save(
+    save_counter(
         deps.storage,
+        env,
+        DEFAULT_COUNTER,
         &State {
             counter: state.counter,
             minimal_donation: state.minimal_donation,
             donating_parent: parent.as_ref().map(|p| p.donating_period),
+            owner: None,
         },
     )?;
+    OLD_STATE.remove(deps.storage);
 
-    if let Some(parent) = parent {
-        PARENT_DONATION.save(
-            deps.storage,
-            &ParentDonation {
-                address: deps.api.addr_validate(&parent.addr)?,
-                donating_parent_period: parent.donating_period,
-                part: parent.part,
-            }
-        )?;
+    let register = save_parent(deps.branch(), env, parent)?;
+
+    Ok(Response::new().add_messages(register))
+}
+
+/// Moves the singleton state and parent into the default counter. The parent is kept as it is.
+pub fn migrate_0_3_0(deps: DepsMut, env: &Env) -> Result<Response, ContractError> {
+    const OLD_STATE: Item<State> = Item::new("state");
+    const OLD_PARENT_DONATION: Item<ParentDonation> = Item::new("parent_donation");
+
+    let state = OLD_STATE.load(deps.storage)?;
+    save_counter(deps.storage, env, DEFAULT_COUNTER, &state)?;
+    OLD_STATE.remove(deps.storage);
+
+    if let Some(parent) = OLD_PARENT_DONATION.may_load(deps.storage)? {
+        PARENT_DONATION.save(deps.storage, DEFAULT_COUNTER, &parent)?;
+        OLD_PARENT_DONATION.remove(deps.storage);
     }
 
     Ok(Response::new())
 }
 
+/// Switches every stored parent configuration to `payout`.
+fn set_parent_payout(storage: &mut dyn Storage, payout: ParentPayout) -> StdResult<()> {
+    let parents = PARENT_DONATION
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (counter_id, mut parent) in parents {
+        parent.payout = payout.clone();
+        PARENT_DONATION.save(storage, &counter_id, &parent)?;
+    }
+
+    Ok(())
+}
+
+/// Handles swap replies. Withdrawn coins which failed to swap are sent to the owner as they are,
+/// donated ones stay in the contract. Subscriptions whose instalment failed are ended.
+pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
+    if reply.id & INSTALMENT_REPLY_FLAG != 0 {
+        let error = reply.result.into_result().err().unwrap_or_default();
+        return exec::end_failed_instalment(deps, reply.id & !INSTALMENT_REPLY_FLAG, error);
+    }
+
+    let swap = PENDING_SWAPS.load(deps.storage, reply.id)?;
+    PENDING_SWAPS.remove(deps.storage, reply.id);
+
+    let mut resp = Response::new()
+        .add_attribute("action", "swap_reply")
+        .add_attribute("offer", swap.offer.to_string());
+
+    match reply.result {
+        SubMsgResult::Ok(_) => resp = resp.add_attribute("swapped", "true"),
+        SubMsgResult::Err(err) => {
+            resp = resp
+                .add_attribute("swapped", "false")
+                .add_attribute("error", err);
+
+            if let Some(recipient) = swap.recipient {
+                resp = resp.add_message(BankMsg::Send { to_address: recipient.into_string(), amount: vec![swap.offer] });
+            }
+        }
+    }
+
+    Ok(resp)
+}
+
+/// Stores the parent configuration of the default counter, returning the message registering this
+/// contract as its child. Legacy parents don't track their children.
+fn save_parent(deps: DepsMut, env: &Env, parent: Option<Parent>) -> Result<Option<WasmMsg>, ContractError> {
+    let parent = match parent {
+        Some(parent) => parent,
+        None => return Ok(None),
+    };
+
+    let max_depth = max_parent_depth(deps.as_ref())?;
+    let parent = validation::parent(deps.as_ref(), env, parent, max_depth)?;
+    PARENT_DONATION.save(deps.storage, DEFAULT_COUNTER, &parent)?;
+
+    if is_legacy(deps.as_ref(), &parent.address)? {
+        return Ok(None);
+    }
+
+    Ok(Some(WasmMsg::Execute {
+        contract_addr: parent.address.into_string(),
+        msg: to_binary(&ExecMsg::RegisterChild {})?,
+        funds: vec![],
+    }))
+}
+
+/// Whether `addr` is a counting contract from before the parent chains. Fails for contracts which
+/// don't store their version, and for addresses which are not contracts at all.
+pub fn is_legacy(deps: Deps, addr: &Addr) -> StdResult<bool> {
+    let version = CONTRACT.query(&deps.querier, addr.clone())?;
+    Ok(version.contract == CONTRACT_NAME && LEGACY_VERSIONS.contains(&version.version.as_str()))
+}
+
+/// Parent of the counting contract at `addr`. Legacy contracts end the chain, as their parent can't
+/// be queried.
+pub fn query_parent(deps: Deps, addr: &Addr) -> StdResult<Option<Addr>> {
+    if is_legacy(deps, addr)? {
+        return Ok(None);
+    }
+
+    let resp: ParentResp = deps.querier.query_wasm_smart(addr, &QueryMsg::Parent {})?;
+    Ok(resp.parent.map(|parent| Addr::unchecked(parent.addr)))
+}
+
+/// Donates `funds` to `parent` as forwarded through `depth` contracts. Legacy parents only take a
+/// plain `Donate {}`.
+pub fn donate_to_parent(deps: Deps, parent: &Addr, depth: u32, funds: Vec<Coin>) -> StdResult<WasmMsg> {
+    let depth = (!is_legacy(deps, parent)?).then(|| depth);
+
+    Ok(WasmMsg::Execute {
+        contract_addr: parent.to_string(),
+        msg: to_binary(&ExecMsg::Donate { depth, counter_id: None })?,
+        funds,
+    })
+}
+
+/// Replaces the parent of the default counter, restarting its donating period.
+fn update_parent(deps: DepsMut, env: &Env, parent: Option<Parent>) -> Result<Option<WasmMsg>, ContractError> {
+    let mut state = STATE.load(deps.storage, DEFAULT_COUNTER)?;
+    state.donating_parent = parent.as_ref().map(|parent| parent.donating_period);
+    STATE.save(deps.storage, DEFAULT_COUNTER, &state)?;
+
+    if parent.is_none() {
+        PARENT_DONATION.remove(deps.storage, DEFAULT_COUNTER);
+    }
+
+    save_parent(deps, env, parent)
+}
+
+/// Contracts instantiated before the depth limit was introduced use the default one.
+pub fn max_parent_depth(deps: Deps) -> StdResult<u32> {
+    Ok(MAX_PARENT_DEPTH.may_load(deps.storage)?.unwrap_or(DEFAULT_MAX_PARENT_DEPTH))
+}
+
+/// Saves the counter and records its value in the history.
+pub fn save_counter(storage: &mut dyn Storage, env: &Env, id: &str, state: &State) -> StdResult<()> {
+    STATE.save(storage, id, state)?;
+    COUNTER_HISTORY.save(storage, (id, env.block.height), &state.counter)?;
+    prune_history(storage, env, id, PRUNE_BATCH)?;
+    Ok(())
+}
+
+/// Removes up to `limit` of the oldest history entries of the counter which are not needed to tell
+/// its value within the retention window, returning how many were removed.
+pub fn prune_history(storage: &mut dyn Storage, env: &Env, id: &str, limit: u32) -> StdResult<u32> {
+    let retention = match HISTORY_RETENTION.may_load(storage)? {
+        Some(retention) => retention,
+        None => return Ok(0),
+    };
+    let cutoff = env.block.height.saturating_sub(retention);
+
+    // The last entry at or before the cutoff holds the value at the start of the window, so it is kept
+    let heights = COUNTER_HISTORY
+        .prefix(id)
+        .keys(storage, None, Some(Bound::inclusive(cutoff)), Order::Ascending)
+        .take(limit as usize + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let pruned = heights.len().saturating_sub(1);
+    for height in &heights[..pruned] {
+        COUNTER_HISTORY.remove(storage, (id, *height));
+    }
+
+    Ok(pruned as u32)
+}
+
+/// Applies the scheduled minimal donation to the default counter once its height is reached.
+pub fn activate_minimal_donation(storage: &mut dyn Storage, env: &Env, state: &mut State) -> StdResult<Option<Event>> {
+    let scheduled = match SCHEDULED_MINIMAL_DONATION.may_load(storage)? {
+        Some(scheduled) if scheduled.height <= env.block.height => scheduled,
+        _ => return Ok(None),
+    };
+
+    SCHEDULED_MINIMAL_DONATION.remove(storage);
+    state.minimal_donation = scheduled.minimal_donation;
+    STATE.save(storage, DEFAULT_COUNTER, state)?;
+
+    let event = Event::new("minimal_donation_update")
+        .add_attribute("minimal_donation", state.minimal_donation.to_string())
+        .add_attribute("height", scheduled.height.to_string());
+
+    Ok(Some(event))
+}
+
+/// Whether `funds` reach the fixed `minimal` donation, a zero minimum counts any donation.
+pub fn meets_minimal_donation(minimal: &Coin, funds: &[Coin]) -> bool {
+    minimal.amount.is_zero() || funds.iter().any(|coin| coin.denom == minimal.denom && coin.amount >= minimal.amount)
+}
+
+/// A proposal passes if the cast weight reaches the quorum and the yes votes exceed the threshold.
+pub fn proposal_status(proposal: &Proposal, env: &Env) -> ProposalStatus {
+    let cast = proposal.yes + proposal.no;
+
+    if proposal.executed {
+        ProposalStatus::Executed
+    } else if env.block.time < proposal.end {
+        ProposalStatus::Open
+    } else if !cast.is_zero() && cast >= proposal.total_weight * proposal.quorum && proposal.yes > cast * proposal.threshold {
+        ProposalStatus::Passed
+    } else {
+        ProposalStatus::Rejected
+    }
+}
+
+/// Adds `coins` to `total`, merging amounts of the same denom.
+pub fn add_coins(total: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+        match total.iter_mut().find(|total| total.denom == coin.denom) {
+            Some(total) => total.amount += coin.amount,
+            None => total.push(coin.clone()),
+        }
+    }
+}
+
+pub fn coins_to_string(coins: &[Coin]) -> String {
+    coins.iter().map(Coin::to_string).collect::<Vec<_>>().join(",")
+}
+
+/// Subtracts `coins` from `total`, dropping denoms which are used up.
+pub fn sub_coins(total: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+        if let Some(total) = total.iter_mut().find(|total| total.denom == coin.denom) {
+            total.amount = total.amount.saturating_sub(coin.amount);
+        }
+    }
+
+    total.retain(|coin| !coin.amount.is_zero());
+}
+
+/// Funds held in escrow by the contract, which don't belong to the donations.
+pub fn escrowed(deps: Deps) -> StdResult<Vec<Coin>> {
+    let mut escrowed = vec![];
+
+    if let Some(pool) = MATCHING_POOL.may_load(deps.storage)? {
+        add_coins(&mut escrowed, &[pool.remaining]);
+    }
+
+    if let Some(campaigns) = CAMPAIGN_ESCROW.may_load(deps.storage)? {
+        add_coins(&mut escrowed, &campaigns);
+    }
+
+    if let Some(fees) = FEES_ACCRUED.may_load(deps.storage)? {
+        add_coins(&mut escrowed, &fees);
+    }
+
+    if let Some(forwards) = IBC_ESCROW.may_load(deps.storage)? {
+        add_coins(&mut escrowed, &forwards);
+    }
+
+    if let Some(subscriptions) = SUBSCRIPTION_ESCROW.may_load(deps.storage)? {
+        add_coins(&mut escrowed, &subscriptions);
+    }
+
+    if let Some(shares) = PARENT_SHARE_ESCROW.may_load(deps.storage)? {
+        add_coins(&mut escrowed, &shares);
+    }
+
+    Ok(escrowed)
+}
+
+/// Contract balance available to the owner and the parent, which is everything but the escrow.
+pub fn donation_balance(deps: Deps, env: &Env) -> StdResult<Vec<Coin>> {
+    let mut balance = deps.querier.query_all_balances(&env.contract.address)?;
+    sub_coins(&mut balance, &escrowed(deps)?);
+    Ok(balance)
+}
+
+fn vested_fraction(schedule: &VestingSchedule, now: Timestamp) -> Decimal {
+    match *schedule {
+        VestingSchedule::Linear { start, end } if now > start && now < end =>
+            Decimal::from_ratio(now.seconds() - start.seconds(), end.seconds() - start.seconds()),
+        VestingSchedule::Linear { end, .. } if now >= end => Decimal::one(),
+        VestingSchedule::Cliff { at } if now >= at => Decimal::one(),
+        _ => Decimal::zero(),
+    }
+}
+
+/// Returns the unlocked part of all donations received so far and what the owner can withdraw now,
+/// which is the unlocked part minus earlier withdrawals. Without vesting everything is unlocked.
+pub fn vested(deps: Deps, env: &Env) -> StdResult<(Vec<Coin>, Vec<Coin>)> {
+    let withdrawn = WITHDRAWN.may_load(deps.storage)?.unwrap_or_default();
+    let fraction = VESTING.may_load(deps.storage)?
+        .map(|schedule| vested_fraction(&schedule, env.block.time))
+        .unwrap_or_else(Decimal::one);
+
+    let mut unlocked = donation_balance(deps, env)?;
+    add_coins(&mut unlocked, &withdrawn);
+    let unlocked: Vec<Coin> = unlocked.into_iter()
+        .map(|mut coin| {
+            coin.amount = coin.amount * fraction;
+            coin
+        })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+
+    let mut withdrawable = unlocked.clone();
+    sub_coins(&mut withdrawable, &withdrawn);
+
+    Ok((unlocked, withdrawable))
+}
+
 pub mod query {
-    use cosmwasm_std::{Deps, StdResult};
-    use crate::msg::ValueResp;
-    use crate::state::STATE;
+    use cosmwasm_std::{Addr, Deps, Empty, Env, Order, StdError, StdResult, Timestamp};
+    use cw_storage_plus::{Bound, Map};
+    use crate::msg::{AncestorsResp, CampaignResp, ChildResp, ChildrenResp, ConfigResp, ContributionResp, CounterResp,
+                     CountersResp, CreditsResp, DonorMode, DonorsResp, FeeConfig, MatchedResp, MatchingPoolInfo,
+                     MatchingPoolResp, MilestoneResp, MilestonesResp, Parent, ParentResp, ReceiptResp,
+                     ReceiptsResp, ValueResp, VestingResp, ValueAtResp, HistoryEntry, ValueHistoryResp, ProposalResp,
+                     ProposalsResp, VoteResp, VotesResp, VotingWeightResp, IbcChannelResp, IbcChannelsResp,
+                     MinimalDonationResp, ParentShareResp, IbcAllowlistResp, IbcCounterparty, SubscriptionResp,
+                     SubscriptionsResp};
+    use crate::state::{ALLOWLIST, CAMPAIGNS, CHILDREN, CONTRIBUTIONS, COUNTER_HISTORY, CREDITS, DEFAULT_COUNTER, DENYLIST,
+                       DONOR_MODE, FEE, FEES_ACCRUED, FEES_COLLECTED, HISTORY_RETENTION, MATCHED, MATCHING_POOL, MILESTONES,
+                       NEXT_CAMPAIGN_ID, OWNER, PARENT_DONATION, RECEIPTS, RECEIPTS_BY_OWNER, STATE, State, VESTING,
+                       VOUCHER_SIGNER, WITHDRAWN, PROPOSALS, BALLOTS, VOTING_WEIGHTS, TOTAL_WEIGHT, Proposal,
+                       IBC_CHANNELS, IBC_PARENT, SCHEDULED_MINIMAL_DONATION, ORACLE, SWAP, SUBSCRIPTIONS, PARENT_SHARES,
+                       IBC_ALLOWLIST,
+                       SUBSCRIPTIONS_BY_DONOR, SUBSCRIPTIONS_BY_DUE};
+    use super::{max_parent_depth, proposal_status, query_parent, vested};
+
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 30;
 
     pub fn value(deps: Deps) -> StdResult<ValueResp> {
-        let value = STATE.load(deps.storage)?.counter;
+        let value = STATE.load(deps.storage, DEFAULT_COUNTER)?.counter;
         Ok(ValueResp { value })
     }
 
-    pub fn incremented(value: u64) -> ValueResp {
-        ValueResp { value: value + 1 }
+    /// Parent of the default counter
+    pub fn parent(deps: Deps) -> StdResult<ParentResp> {
+        Ok(ParentResp { parent: counter_parent(deps, DEFAULT_COUNTER)? })
     }
-}
 
-pub mod exec {
-    use cosmwasm_std::{BankMsg, DepsMut, Env, MessageInfo, Response, StdResult, to_binary, WasmMsg};
-    use crate::error::ContractError;
-    use crate::ExecMsg;
-    use crate::state::{STATE, OWNER, PARENT_DONATION};
+    fn counter_parent(deps: Deps, id: &str) -> StdResult<Option<Parent>> {
+        let parent = PARENT_DONATION.may_load(deps.storage, id)?
+            .map(|parent| Parent {
+                addr: parent.address.into_string(),
+                donating_period: parent.donating_parent_period,
+                part: parent.part,
+                mode: parent.mode,
+                payout: parent.payout,
+            });
 
-    pub fn donate(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
-        let mut state = STATE.load(deps.storage)?;
-        let mut resp = Response::default();
+        Ok(parent)
+    }
 
-        if state.minimal_donation.amount.is_zero()
-            || info.funds.iter().any(|coin| {coin.denom != state.minimal_donation.denom
-            && coin.amount >= state.minimal_donation.amount}) {
-            state.counter += 1;
+    pub fn counter(deps: Deps, id: String) -> StdResult<CounterResp> {
+        let state = STATE.load(deps.storage, &id)?;
+        counter_resp(deps, id, state)
+    }
 
-            if let Some(parent) = &mut state.donating_parent {
-                *parent -= 1;
-                if *parent == 0 {
-                    let parent_donation = PARENT_DONATION.load(deps.storage)?;
-                    *parent = parent_donation.donating_parent_period;
-
-                    let funds = deps.querier.query_all_balances(env.contract.address)?
-                        .into_iter().map(|mut coin| {
-                        coin.amount = coin.amount * parent_donation.part;
-                        coin
-                    }).collect();
-
-                    let msg = WasmMsg::Execute {
-                        contract_addr: parent_donation.address.to_string(),
-                        msg: to_binary(&ExecMsg::Donate {})?,
-                        funds,
-                    };
-
-                    resp = resp.add_message(msg);
-                    resp = resp.add_attribute("donated_to_parent", parent_donation.address.to_string());
-                }
+    pub fn counters(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<CountersResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let min = start_after.as_deref().map(Bound::exclusive);
+
+        let counters = STATE
+            .range(deps.storage, min, None, Order::Ascending)
+            .take(limit)
+            .map(|counter| {
+                let (id, state) = counter?;
+                counter_resp(deps, id, state)
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(CountersResp { counters })
+    }
+
+    fn counter_resp(deps: Deps, id: String, state: State) -> StdResult<CounterResp> {
+        Ok(CounterResp {
+            parent: counter_parent(deps, &id)?,
+            id,
+            value: state.counter,
+            minimal_donation: state.minimal_donation,
+            owner: state.owner.map(Addr::into_string),
+        })
+    }
+
+    pub fn children(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<ChildrenResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+        let min = start_after.as_ref().map(Bound::exclusive);
+
+        let children = CHILDREN
+            .range(deps.storage, min, None, Order::Ascending)
+            .take(limit)
+            .map(|child| {
+                let (addr, info) = child?;
+                Ok(ChildResp { addr: addr.into_string(), received: info.received, approved: info.approved })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(ChildrenResp { children })
+    }
+
+    /// Walks the parent chain, bounded by the maximal parent depth of this contract.
+    pub fn ancestors(deps: Deps) -> StdResult<AncestorsResp> {
+        let max_depth = max_parent_depth(deps)? as usize;
+        let mut ancestors = vec![];
+        let mut current = PARENT_DONATION.may_load(deps.storage, DEFAULT_COUNTER)?.map(|parent| parent.address);
+
+        while let Some(addr) = current {
+            if ancestors.len() >= max_depth {
+                break;
             }
-            STATE.save(deps.storage, &state)?;
+
+            current = query_parent(deps, &addr)?;
+            ancestors.push(addr.into_string());
         }
 
-        resp = resp
-            .add_attribute("action", "donate")
-            .add_attribute("sender", info.sender.as_str())
-            .add_attribute("counter", state.counter.to_string());
+        Ok(AncestorsResp { ancestors })
+    }
 
-        Ok(resp)
+    pub fn receipts(deps: Deps, owner: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<ReceiptsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let owner = deps.api.addr_validate(&owner)?;
+
+        let receipts = RECEIPTS_BY_OWNER
+            .prefix(&owner)
+            .keys(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|id| receipt(deps, id?))
+            .collect::<StdResult<_>>()?;
+
+        Ok(ReceiptsResp { receipts })
     }
 
-    pub fn reset(deps: DepsMut, info: MessageInfo, counter: u64) -> StdResult<Response> {
-        STATE.update(
-            deps.storage,
-            |mut state| -> StdResult<_> {
-                state.counter = counter;
-                Ok(state)
-            }
-        )?;
+    pub fn receipt(deps: Deps, id: u64) -> StdResult<ReceiptResp> {
+        let receipt = RECEIPTS.load(deps.storage, id)?;
 
-        let resp = Response::new()
-            .add_attribute("action", "reset")
-            .add_attribute("sender", info.sender.as_str())
-            .add_attribute("counter", counter.to_string());
+        Ok(ReceiptResp {
+            id,
+            owner: receipt.owner.into_string(),
+            counter: receipt.counter,
+            amount: receipt.amount,
+            time: receipt.time,
+        })
+    }
+
+    /// Lists milestones which were already reached if `achieved` is set, or the upcoming ones otherwise.
+    pub fn milestones(deps: Deps, achieved: bool, start_after: Option<u64>, limit: Option<u32>) -> StdResult<MilestonesResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let milestones = MILESTONES
+            .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .filter(|milestone| match milestone {
+                Ok((_, milestone)) => milestone.achieved.is_some() == achieved,
+                Err(_) => true,
+            })
+            .take(limit)
+            .map(|milestone| {
+                let (counter, milestone) = milestone?;
+                let (achieved_by, achieved_at) = match milestone.achieved {
+                    Some(achievement) => (Some(achievement.donor.into_string()), Some(achievement.height)),
+                    None => (None, None),
+                };
+
+                Ok(MilestoneResp { counter, action: milestone.action, achieved_by, achieved_at })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(MilestonesResp { milestones })
+    }
+
+    pub fn matching_pool(deps: Deps) -> StdResult<MatchingPoolResp> {
+        let pool = MATCHING_POOL.may_load(deps.storage)?
+            .map(|pool| MatchingPoolInfo {
+                id: pool.id,
+                sponsor: pool.sponsor.into_string(),
+                remaining: pool.remaining,
+                ratio: pool.ratio,
+                cap: pool.cap,
+                expires_at: pool.expires_at,
+            });
+
+        Ok(MatchingPoolResp { pool })
+    }
+
+    pub fn matched(deps: Deps, pool_id: u64, donor: String) -> StdResult<MatchedResp> {
+        let donor = deps.api.addr_validate(&donor)?;
+        let amount = MATCHED.may_load(deps.storage, (pool_id, &donor))?.unwrap_or_default();
+        Ok(MatchedResp { amount })
+    }
+
+    pub fn campaign(deps: Deps, id: Option<u64>) -> StdResult<CampaignResp> {
+        let id = match id {
+            Some(id) => id,
+            None => NEXT_CAMPAIGN_ID.may_load(deps.storage)?
+                .and_then(|next| next.checked_sub(1))
+                .ok_or_else(|| StdError::not_found("Campaign"))?,
+        };
+        let campaign = CAMPAIGNS.load(deps.storage, id)?;
+
+        Ok(CampaignResp {
+            id,
+            start: campaign.start,
+            end: campaign.end,
+            goal: campaign.goal,
+            raised: campaign.raised,
+            release: campaign.release,
+            status: campaign.status,
+        })
+    }
+
+    pub fn campaign_contribution(deps: Deps, campaign_id: u64, donor: String) -> StdResult<ContributionResp> {
+        let donor = deps.api.addr_validate(&donor)?;
+        let amount = CONTRIBUTIONS.may_load(deps.storage, (campaign_id, &donor))?.unwrap_or_default();
+        Ok(ContributionResp { amount })
+    }
+
+    pub fn vesting(deps: Deps, env: Env) -> StdResult<VestingResp> {
+        let (unlocked, withdrawable) = vested(deps, &env)?;
+
+        Ok(VestingResp {
+            schedule: VESTING.may_load(deps.storage)?,
+            unlocked,
+            withdrawn: WITHDRAWN.may_load(deps.storage)?.unwrap_or_default(),
+            withdrawable,
+        })
+    }
+
+    pub fn config(deps: Deps) -> StdResult<ConfigResp> {
+        let fee = FEE.may_load(deps.storage)?
+            .map(|fee| FeeConfig {
+                recipient: fee.recipient.into_string(),
+                rate: fee.rate,
+                mode: fee.mode,
+            });
+
+        Ok(ConfigResp {
+            owner: OWNER.load(deps.storage)?.into_string(),
+            minimal_donation: STATE.load(deps.storage, DEFAULT_COUNTER)?.minimal_donation,
+            max_parent_depth: max_parent_depth(deps)?,
+            fee,
+            fees_collected: FEES_COLLECTED.may_load(deps.storage)?.unwrap_or_default(),
+            fees_accrued: FEES_ACCRUED.may_load(deps.storage)?.unwrap_or_default(),
+            donor_mode: DONOR_MODE.may_load(deps.storage)?.unwrap_or(DonorMode::Open {}),
+            voucher_signer: VOUCHER_SIGNER.may_load(deps.storage)?,
+            history_retention: HISTORY_RETENTION.may_load(deps.storage)?,
+            ibc_parent: IBC_PARENT.may_load(deps.storage)?,
+            oracle: ORACLE.may_load(deps.storage)?,
+            swap: SWAP.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn value_at(deps: Deps, height: u64, counter_id: Option<String>) -> StdResult<ValueAtResp> {
+        let counter_id = counter_id.as_deref().unwrap_or(DEFAULT_COUNTER);
+
+        let value = COUNTER_HISTORY
+            .prefix(counter_id)
+            .range(deps.storage, None, Some(Bound::inclusive(height)), Order::Descending)
+            .next()
+            .transpose()?
+            .map(|(_, value)| value);
+
+        Ok(ValueAtResp { value })
+    }
+
+    pub fn value_history(deps: Deps, counter_id: Option<String>, start_after: Option<u64>, limit: Option<u32>)
+        -> StdResult<ValueHistoryResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let counter_id = counter_id.as_deref().unwrap_or(DEFAULT_COUNTER);
+
+        let history = COUNTER_HISTORY
+            .prefix(counter_id)
+            .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|entry| entry.map(|(height, value)| HistoryEntry { height, value }))
+            .collect::<StdResult<_>>()?;
+
+        Ok(ValueHistoryResp { history })
+    }
+
+    pub fn credits(deps: Deps, donor: String) -> StdResult<CreditsResp> {
+        let donor = deps.api.addr_validate(&donor)?;
+        let amount = CREDITS.may_load(deps.storage, &donor)?.unwrap_or_default();
+        Ok(CreditsResp { amount })
+    }
+
+    pub fn allowlist(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<DonorsResp> {
+        let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+        donors(deps, &ALLOWLIST, start_after.as_ref(), limit)
+    }
+
+    pub fn denylist(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<DonorsResp> {
+        let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+        donors(deps, &DENYLIST, start_after.as_ref(), limit)
+    }
+
+    fn donors<'a>(deps: Deps, list: &Map<&'a Addr, Empty>, start_after: Option<&'a Addr>, limit: Option<u32>)
+        -> StdResult<DonorsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let donors = list
+            .keys(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|addr| addr.map(Addr::into_string))
+            .collect::<StdResult<_>>()?;
+
+        Ok(DonorsResp { donors })
+    }
+
+    pub fn proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResp> {
+        let proposal = PROPOSALS.load(deps.storage, id)?;
+        Ok(proposal_resp(&env, id, proposal))
+    }
+
+    pub fn proposals(deps: Deps, env: Env, start_after: Option<u64>, limit: Option<u32>) -> StdResult<ProposalsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let proposals = PROPOSALS
+            .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|proposal| proposal.map(|(id, proposal)| proposal_resp(&env, id, proposal)))
+            .collect::<StdResult<_>>()?;
+
+        Ok(ProposalsResp { proposals })
+    }
+
+    fn proposal_resp(env: &Env, id: u64, proposal: Proposal) -> ProposalResp {
+        ProposalResp {
+            id,
+            status: proposal_status(&proposal, env),
+            proposer: proposal.proposer.into_string(),
+            action: proposal.action,
+            end: proposal.end,
+            total_weight: proposal.total_weight,
+            yes: proposal.yes,
+            no: proposal.no,
+        }
+    }
+
+    pub fn votes(deps: Deps, proposal_id: u64, start_after: Option<String>, limit: Option<u32>) -> StdResult<VotesResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+        let min = start_after.as_ref().map(Bound::exclusive);
+
+        let votes = BALLOTS
+            .prefix(proposal_id)
+            .range(deps.storage, min, None, Order::Ascending)
+            .take(limit)
+            .map(|ballot| {
+                let (voter, ballot) = ballot?;
+                Ok(VoteResp { voter: voter.into_string(), option: ballot.option, weight: ballot.weight })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(VotesResp { votes })
+    }
+
+    pub fn voting_weight(deps: Deps, donor: String) -> StdResult<VotingWeightResp> {
+        let donor = deps.api.addr_validate(&donor)?;
+
+        Ok(VotingWeightResp {
+            weight: VOTING_WEIGHTS.may_load(deps.storage, &donor)?.unwrap_or_default(),
+            total: TOTAL_WEIGHT.may_load(deps.storage)?.unwrap_or_default(),
+        })
+    }
+
+    pub fn minimal_donation(deps: Deps, env: Env) -> StdResult<MinimalDonationResp> {
+        let current = STATE.load(deps.storage, DEFAULT_COUNTER)?.minimal_donation;
+
+        let resp = match SCHEDULED_MINIMAL_DONATION.may_load(deps.storage)? {
+            // Only stored by the next donation, but already in effect
+            Some(scheduled) if scheduled.height <= env.block.height =>
+                MinimalDonationResp { current: scheduled.minimal_donation, pending: None },
+            pending => MinimalDonationResp { current, pending },
+        };
 
         Ok(resp)
     }
 
-    pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    pub fn ibc_channels(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<IbcChannelsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let min = start_after.as_deref().map(Bound::exclusive);
+
+        let channels = IBC_CHANNELS
+            .range(deps.storage, min, None, Order::Ascending)
+            .take(limit)
+            .map(|channel| {
+                let (channel_id, info) = channel?;
+                Ok(IbcChannelResp {
+                    channel_id,
+                    counterparty_port_id: info.counterparty.port_id,
+                    counterparty_channel_id: info.counterparty.channel_id,
+                    received: info.received,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(IbcChannelsResp { channels })
+    }
+
+    pub fn parent_share(deps: Deps, parent: String) -> StdResult<ParentShareResp> {
+        let parent = deps.api.addr_validate(&parent)?;
+        let accrued = PARENT_SHARES.may_load(deps.storage, &parent)?.unwrap_or_default();
+        Ok(ParentShareResp { accrued })
+    }
+
+    pub fn ibc_allowlist(deps: Deps, start_after: Option<IbcCounterparty>, limit: Option<u32>)
+        -> StdResult<IbcAllowlistResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let min = start_after.as_ref()
+            .map(|counterparty| Bound::exclusive((counterparty.connection_id.as_str(), counterparty.port_id.as_str())));
+
+        let counterparties = IBC_ALLOWLIST
+            .keys(deps.storage, min, None, Order::Ascending)
+            .take(limit)
+            .map(|key| key.map(|(connection_id, port_id)| IbcCounterparty { connection_id, port_id }))
+            .collect::<StdResult<_>>()?;
+
+        Ok(IbcAllowlistResp { counterparties })
+    }
+
+    pub fn subscription(deps: Deps, id: u64) -> StdResult<SubscriptionResp> {
+        let subscription = SUBSCRIPTIONS.load(deps.storage, id)?;
+
+        Ok(SubscriptionResp {
+            id,
+            donor: subscription.donor.into_string(),
+            counter_id: subscription.counter_id,
+            amount: subscription.amount,
+            interval: subscription.interval,
+            next_due: subscription.next_due,
+            remaining: subscription.remaining,
+        })
+    }
+
+    pub fn subscriptions(deps: Deps, donor: String, start_after: Option<u64>, limit: Option<u32>)
+        -> StdResult<SubscriptionsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let donor = deps.api.addr_validate(&donor)?;
+
+        let subscriptions = SUBSCRIPTIONS_BY_DONOR
+            .prefix(&donor)
+            .keys(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .map(|id| subscription(deps, id?))
+            .collect::<StdResult<_>>()?;
+
+        Ok(SubscriptionsResp { subscriptions })
+    }
+
+    pub fn subscriptions_by_due(deps: Deps, start_after: Option<(Timestamp, u64)>, limit: Option<u32>)
+        -> StdResult<SubscriptionsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let min = start_after.map(|(due, id)| Bound::exclusive((due.nanos(), id)));
+
+        let subscriptions = SUBSCRIPTIONS_BY_DUE
+            .keys(deps.storage, min, None, Order::Ascending)
+            .take(limit)
+            .map(|key| subscription(deps, key?.1))
+            .collect::<StdResult<_>>()?;
+
+        Ok(SubscriptionsResp { subscriptions })
+    }
+
+    pub fn incremented(value: u64) -> ValueResp {
+        ValueResp { value: value + 1 }
+    }
+}
+
+pub mod exec {
+    use cosmwasm_std::{Addr, Api, BankMsg, Binary, Coin, coin, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Event,
+                       Fraction, MessageInfo, Order, Response, StdResult, Storage, SubMsg, Timestamp, to_binary, to_vec,
+                       Uint128, Uint256, WasmMsg};
+    use cw_storage_plus::{Bound, Map};
+    use sha2::{Digest, Sha256};
+    use crate::error::ContractError;
+    use crate::ExecMsg;
+    use crate::ibc;
+    use crate::msg::{AdminMsg, CampaignRelease, CampaignStatus, Cw721ExecMsg, DonorMode, FeeMode, ForwardMode,
+                     GovernanceConfig, IbcCounterparty, IbcParent, MilestoneAction, OracleConfig, OracleQueryMsg,
+                     Parent, ParentPayout, ParentResp, PriceResp, ProposalAction, ProposalStatus, QueryMsg,
+                     ReceiptExtension, ReceiptMode, ScheduledMinimalDonation, SimulationResp, SwapConfig, SwapExecMsg,
+                     SwapQueryMsg, SwapTrigger, VoteOption, Voucher, VoucherSigner};
+    use crate::state::{STATE, DEFAULT_COUNTER, OWNER, PARENT_DONATION, CHILDREN, RECEIPT_CONFIG, RECEIPTS,
+                       RECEIPTS_BY_OWNER, NEXT_RECEIPT_ID, MILESTONES, MATCHING_POOL, NEXT_MATCHING_POOL_ID, MATCHED,
+                       CAMPAIGNS, ACTIVE_CAMPAIGN, NEXT_CAMPAIGN_ID, CONTRIBUTIONS, CAMPAIGN_ESCROW, WITHDRAWN, FEE,
+                       FEES_COLLECTED, FEES_ACCRUED, DONOR_MODE, ALLOWLIST, DENYLIST, VOUCHER_SIGNER, VOUCHER_NONCES,
+                       CREDITS, HISTORY_RETENTION, GOVERNANCE, VOTING_WEIGHTS, TOTAL_WEIGHT, PROPOSALS,
+                       NEXT_PROPOSAL_ID, BALLOTS, VOTING_WEIGHT_HISTORY, TOTAL_WEIGHT_HISTORY, IBC_ALLOWLIST,
+                       IBC_PARENT, SCHEDULED_MINIMAL_DONATION, ORACLE, SWAP, PENDING_SWAPS, NEXT_SWAP_ID, PendingSwap,
+                       SUBSCRIPTIONS, SUBSCRIPTIONS_BY_DONOR, SUBSCRIPTIONS_BY_DUE, NEXT_SUBSCRIPTION_ID,
+                       SUBSCRIPTION_ESCROW, Subscription, PARENT_SHARES, PARENT_SHARE_ESCROW, Achievement, Ballot,
+                       Campaign, MatchingPool, Milestone, Proposal, Receipt, ReceiptConfig, State};
+    use crate::validation;
+    use super::{activate_minimal_donation, add_coins, coins_to_string, donate_to_parent, donation_balance,
+                INSTALMENT_REPLY_FLAG, max_parent_depth, meets_minimal_donation, proposal_status, save_counter,
+                sub_coins, vested};
+
+    fn ensure_owner(deps: Deps, sender: &Addr) -> Result<Addr, ContractError> {
         let owner = OWNER.load(deps.storage)?;
-        if info.sender != owner {
-            return Err(ContractError::Unauthorized { owner: owner.to_string()});
+        if *sender != owner {
+            return Err(ContractError::Unauthorized { owner: owner.into_string() });
         }
 
-        let funds = deps.querier.query_all_balances(&env.contract.address)?;
-        let bank_msg = BankMsg::Send { to_address: owner.to_string(), amount: funds, };
-        let resp = Response::new()
-            .add_message(bank_msg)
-            .add_attribute("action", "withdraw")
-            .add_attribute("sender", info.sender.as_str());
+        Ok(owner)
+    }
 
+    fn ensure_donor_allowed(deps: Deps, donor: &Addr) -> Result<(), ContractError> {
+        let allowed = !DENYLIST.has(deps.storage, donor) && match DONOR_MODE.may_load(deps.storage)? {
+            None | Some(DonorMode::Open {}) => true,
+            Some(DonorMode::Allowlist {}) => ALLOWLIST.has(deps.storage, donor),
+        };
 
-        Ok(resp)
+        if !allowed {
+            return Err(ContractError::DonorNotAllowed { donor: donor.to_string() });
+        }
+
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// Donations in the denom of the default counter's minimal donation give the donor voting weight
+    fn record_voting_weight(storage: &mut dyn Storage, env: &Env, donor: &Addr, funds: &[Coin]) -> StdResult<()> {
+        let denom = STATE.load(storage, DEFAULT_COUNTER)?.minimal_donation.denom;
+        let amount = funds.iter()
+            .find(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        let weight = VOTING_WEIGHTS.may_load(storage, donor)?.unwrap_or_default();
+        VOTING_WEIGHTS.save(storage, donor, &(weight + amount))?;
+        VOTING_WEIGHT_HISTORY.save(storage, (donor, env.block.height), &(weight + amount))?;
+
+        let total = TOTAL_WEIGHT.may_load(storage)?.unwrap_or_default();
+        TOTAL_WEIGHT.save(storage, &(total + amount))?;
+        TOTAL_WEIGHT_HISTORY.save(storage, env.block.height, &(total + amount))
+    }
+
+    /// Voting weight of `donor` at the start of the block at `height`
+    fn voting_weight_at(storage: &dyn Storage, donor: &Addr, height: u64) -> StdResult<Uint128> {
+        let weight = VOTING_WEIGHT_HISTORY.prefix(donor)
+            .range(storage, None, Some(Bound::exclusive(height)), Order::Descending)
+            .next()
+            .transpose()?;
+
+        Ok(weight.map(|(_, weight)| weight).unwrap_or_default())
+    }
+
+    /// Total voting weight at the start of the block at `height`
+    fn total_weight_at(storage: &dyn Storage, height: u64) -> StdResult<Uint128> {
+        let total = TOTAL_WEIGHT_HISTORY
+            .range(storage, None, Some(Bound::exclusive(height)), Order::Descending)
+            .next()
+            .transpose()?;
+
+        Ok(total.map(|(_, total)| total).unwrap_or_default())
+    }
+
+    fn load_counter(storage: &dyn Storage, id: &str) -> Result<State, ContractError> {
+        STATE.may_load(storage, id)?.ok_or_else(|| ContractError::UnknownCounter { id: id.to_owned() })
+    }
+
+    pub fn donate(mut deps: DepsMut, env: Env, info: MessageInfo, depth: Option<u32>, counter_id: Option<String>)
+        -> Result<Response, ContractError> {
+        // Only approved children are trusted with the depth of what they forward, anyone else donates
+        // directly
+        let child = CHILDREN.may_load(deps.storage, &info.sender)?;
+        let depth = match &child {
+            Some(child) if child.approved => depth.unwrap_or(0),
+            _ => 0,
+        };
+        let max_depth = max_parent_depth(deps.as_ref())?;
+        if depth > max_depth {
+            return Err(ContractError::ParentDepthExceeded { max_depth });
+        }
+
+        let counter_id = counter_id.unwrap_or_else(|| DEFAULT_COUNTER.to_owned());
+        let mut state = load_counter(deps.storage, &counter_id)?;
+        let activated = if counter_id == DEFAULT_COUNTER {
+            activate_minimal_donation(deps.storage, &env, &mut state)?
+        } else {
+            None
+        };
+
+        match child {
+            Some(mut child) => {
+                // Anyone can register a child forwarding donations, only the approved ones are trusted
+                // to check their donors
+                if !child.approved {
+                    ensure_donor_allowed(deps.as_ref(), &info.sender)?;
+                }
+
+                add_coins(&mut child.received, &info.funds);
+                CHILDREN.save(deps.storage, &info.sender, &child)?;
+            }
+            None => {
+                ensure_donor_allowed(deps.as_ref(), &info.sender)?;
+                record_voting_weight(deps.storage, &env, &info.sender, &info.funds)?;
+            }
+        }
+
+        let mut resp = Response::default().add_events(activated);
+
+        // Fees sent with this response are still part of the balance until the message executes.
+        // Forwarded donations were already charged by the contract they were made to.
+        let (fee, fee_msg) = if depth == 0 {
+            charge_fee(deps.storage, &info.funds)?
+        } else {
+            (vec![], None)
+        };
+        let mut outgoing = vec![];
+        if !fee.is_empty() {
+            resp = resp.add_attribute("fee", coins_to_string(&fee));
+        }
+        if let Some(msg) = fee_msg {
+            add_coins(&mut outgoing, &fee);
+            resp = resp.add_message(msg);
+        }
+
+        let mut funds = info.funds.clone();
+        sub_coins(&mut funds, &fee);
+
+        // The oracle only normalizes the minimal donation of the default counter
+        let oracle = if counter_id == DEFAULT_COUNTER {
+            ORACLE.may_load(deps.storage)?
+        } else {
+            None
+        };
+
+        let counted = match oracle {
+            Some(oracle) => {
+                let (counted, value) = normalize_donation(deps.as_ref(), &env, &oracle, &funds)?;
+                resp = resp.add_attribute("normalized_value", value.to_string());
+                counted
+            }
+            None => meets_minimal_donation(&state.minimal_donation, &funds),
+        };
+
+        if counted {
+            state.counter += 1;
+
+            // Receipts are only issued to donors, not to children forwarding their share
+            if depth == 0 {
+                if let Some(config) = RECEIPT_CONFIG.may_load(deps.storage)? {
+                    let (id, msg) = mint_receipt(deps.storage, &env, &info, state.counter, config)?;
+                    resp = resp
+                        .add_messages(msg)
+                        .add_attribute("receipt_id", id.to_string());
+                }
+
+                if let Some(matched) = match_donation(deps.storage, &env, &info.sender, &funds)? {
+                    resp = resp.add_attribute("matched", matched.to_string());
+                }
+
+                if let Some(id) = contribute_to_campaign(deps.storage, &env, &info.sender, &funds)? {
+                    resp = resp.add_attribute("campaign", id.to_string());
+                }
+            }
+
+            if let Some(parent) = &mut state.donating_parent {
+                let parent_donation = PARENT_DONATION.load(deps.storage, &counter_id)?;
+                let mut balance = donation_balance(deps.as_ref(), &env)?;
+                sub_coins(&mut balance, &outgoing);
+
+                let forwarded: Option<Vec<Coin>> = match parent_donation.mode {
+                    ForwardMode::Period => {
+                        *parent -= 1;
+                        (*parent == 0).then(|| {
+                            *parent = parent_donation.donating_parent_period;
+                            balance.into_iter()
+                                .map(|mut coin| {
+                                    coin.amount = coin.amount * parent_donation.part;
+                                    coin
+                                })
+                                .filter(|coin| !coin.amount.is_zero())
+                                .collect()
+                        })
+                    }
+                    ForwardMode::Stream => Some(stream_share(&funds, &balance, parent_donation.part)),
+                };
+
+                match (forwarded, parent_donation.payout) {
+                    (Some(forwarded), ParentPayout::Push) => {
+                        let msg = donate_to_parent(deps.as_ref(), &parent_donation.address, depth + 1, forwarded.clone())?;
+
+                        add_coins(&mut outgoing, &forwarded);
+                        resp = resp.add_message(msg);
+                        resp = resp.add_attribute("donated_to_parent", parent_donation.address.to_string());
+                    }
+                    // Escrowed until claimed, so it is not outgoing here
+                    (Some(forwarded), ParentPayout::Pull) if !forwarded.is_empty() => {
+                        let mut share = PARENT_SHARES.may_load(deps.storage, &parent_donation.address)?.unwrap_or_default();
+                        add_coins(&mut share, &forwarded);
+                        PARENT_SHARES.save(deps.storage, &parent_donation.address, &share)?;
+
+                        let mut escrow = PARENT_SHARE_ESCROW.may_load(deps.storage)?.unwrap_or_default();
+                        add_coins(&mut escrow, &forwarded);
+                        PARENT_SHARE_ESCROW.save(deps.storage, &escrow)?;
+
+                        resp = resp.add_attribute("accrued_to_parent", parent_donation.address.to_string());
+                    }
+                    _ => (),
+                }
+            } else if counter_id == DEFAULT_COUNTER {
+                if let Some(parent) = IBC_PARENT.may_load(deps.storage)? {
+                    let mut balance = donation_balance(deps.as_ref(), &env)?;
+                    sub_coins(&mut balance, &outgoing);
+
+                    // Escrowed until the parent acknowledges the notice, so it is not outgoing here
+                    let forwarded = stream_share(&funds, &balance, parent.part);
+                    if !forwarded.is_empty() {
+                        let msg = ibc::forward_donation(deps.storage, &env, &parent, forwarded)?;
+                        resp = resp
+                            .add_message(msg)
+                            .add_attribute("donated_to_parent", &parent.addr);
+                    }
+                }
+            }
+
+            // Milestones are only tracked for the default counter
+            if counter_id == DEFAULT_COUNTER {
+                let reached = reach_milestones(deps.branch(), &env, &info.sender, state.counter - 1, state.counter, &mut outgoing)?;
+                for (event, msg) in reached {
+                    resp = resp.add_event(event).add_messages(msg);
+                }
+            }
+
+            save_counter(deps.storage, &env, &counter_id, &state)?;
+        }
+
+        // Only what is left of the donation after everything else is swapped
+        if let Some(config) = SWAP.may_load(deps.storage)?.filter(|config| config.trigger == SwapTrigger::Donate {}) {
+            let mut available = donation_balance(deps.as_ref(), &env)?;
+            sub_coins(&mut available, &outgoing);
+
+            let (msgs, _) = swap_coins(deps.branch(), &config, stream_share(&funds, &available, Decimal::one()), None)?;
+            resp = resp.add_submessages(msgs);
+        }
+
+        resp = resp
+            .add_attribute("action", "donate")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter_id", &counter_id)
+            .add_attribute("counter", state.counter.to_string());
+
+        Ok(resp)
+    }
+
+    /// Returns submessages swapping the coins which are not in the treasury denom, and the coins
+    /// kept as they are. Coins the swap contract fails to simulate are kept too.
+    fn swap_coins(deps: DepsMut, config: &SwapConfig, coins: Vec<Coin>, recipient: Option<&Addr>)
+        -> Result<(Vec<SubMsg>, Vec<Coin>), ContractError> {
+        let mut msgs = vec![];
+        let mut kept = vec![];
+
+        for offer in coins {
+            if offer.denom == config.treasury_denom {
+                kept.push(offer);
+                continue;
+            }
+
+            let simulation = deps.querier.query_wasm_smart::<SimulationResp>(
+                &config.contract,
+                &SwapQueryMsg::Simulate { offer: offer.clone(), ask_denom: config.treasury_denom.clone() },
+            );
+            let min_output = match simulation {
+                Ok(simulation) if !simulation.amount.is_zero() =>
+                    simulation.amount * (Decimal::one() - config.max_slippage),
+                _ => {
+                    kept.push(offer);
+                    continue;
+                }
+            };
+
+            let id = NEXT_SWAP_ID.may_load(deps.storage)?.unwrap_or_default();
+            NEXT_SWAP_ID.save(deps.storage, &(id + 1))?;
+            PENDING_SWAPS.save(deps.storage, id, &PendingSwap { offer: offer.clone(), recipient: recipient.cloned() })?;
+
+            let msg = WasmMsg::Execute {
+                contract_addr: config.contract.clone(),
+                msg: to_binary(&SwapExecMsg::Swap {
+                    ask_denom: config.treasury_denom.clone(),
+                    min_output,
+                    recipient: recipient.map(Addr::to_string),
+                })?,
+                funds: vec![offer],
+            };
+            msgs.push(SubMsg::reply_always(msg, id));
+        }
+
+        Ok((msgs, kept))
+    }
+
+    /// Values the listed denoms of `funds` in reference units. Returns if any of them meets the
+    /// minimum, and the total value.
+    fn normalize_donation(deps: Deps, env: &Env, oracle: &OracleConfig, funds: &[Coin])
+        -> Result<(bool, Uint128), ContractError> {
+        let mut counted = false;
+        let mut total = Uint128::zero();
+
+        for coin in funds.iter().filter(|coin| oracle.denoms.contains(&coin.denom)) {
+            let resp: PriceResp = deps.querier.query_wasm_smart(
+                &oracle.oracle,
+                &OracleQueryMsg::Price { denom: coin.denom.clone() },
+            )?;
+
+            // Subtracted from the block time, as adding to the update time reported by the oracle can overflow
+            if env.block.time.seconds().saturating_sub(resp.updated_at.seconds()) > oracle.max_age {
+                return Err(ContractError::StalePrice { denom: coin.denom.clone(), updated_at: resp.updated_at });
+            }
+
+            let overflow = || ContractError::ValueOverflow { denom: coin.denom.clone() };
+            let value = coin.amount.full_mul(resp.price.numerator()) / Uint256::from(resp.price.denominator());
+            let value = Uint128::try_from(value).map_err(|_| overflow())?;
+            counted |= value >= oracle.minimum;
+            total = total.checked_add(value).map_err(|_| overflow())?;
+        }
+
+        Ok((counted, total))
+    }
+
+    /// Share of the donated `funds` forwarded in the stream mode. Capped to the balance, so funds
+    /// escrowed for a campaign stay in place.
+    fn stream_share(funds: &[Coin], balance: &[Coin], part: Decimal) -> Vec<Coin> {
+        funds.iter()
+            .map(|donated| {
+                let available = balance.iter()
+                    .find(|coin| coin.denom == donated.denom)
+                    .map(|coin| coin.amount)
+                    .unwrap_or_default();
+                coin((donated.amount * part).min(available).u128(), &donated.denom)
+            })
+            .filter(|coin| !coin.amount.is_zero())
+            .collect()
+    }
+
+    /// Counters with milestones can only be reset by the contract owner, as resetting right below
+    /// a milestone would let anyone reach it.
+    pub fn reset(mut deps: DepsMut, env: Env, info: MessageInfo, counter: u64, counter_id: Option<String>)
+        -> Result<Response, ContractError> {
+        let counter_id = counter_id.unwrap_or_else(|| DEFAULT_COUNTER.to_owned());
+        let mut state = load_counter(deps.storage, &counter_id)?;
+
+        if let Some(owner) = &state.owner {
+            if info.sender != *owner {
+                return Err(ContractError::Unauthorized { owner: owner.to_string() });
+            }
+        }
+
+        // Milestones are only tracked for the default counter
+        let milestones = counter_id == DEFAULT_COUNTER
+            && MILESTONES.keys(deps.storage, None, None, Order::Ascending).next().is_some();
+        if milestones {
+            ensure_owner(deps.as_ref(), &info.sender)?;
+        }
+
+        let previous = state.counter;
+        state.counter = counter;
+        save_counter(deps.storage, &env, &counter_id, &state)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "reset")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter_id", &counter_id)
+            .add_attribute("counter", counter.to_string());
+
+        if milestones && counter > previous {
+            resp = resp.add_events(skip_milestones(deps.storage, &env, &info.sender, previous, counter)?);
+        }
+
+        Ok(resp)
+    }
+
+    /// Marks the milestones crossed by a reset from `from` to `to` as achieved by the `owner`
+    /// without performing their actions, so no bonus is paid for a counter nobody donated to.
+    fn skip_milestones(storage: &mut dyn Storage, env: &Env, owner: &Addr, from: u64, to: u64) -> StdResult<Vec<Event>> {
+        let crossed = MILESTONES
+            .range(storage, Some(Bound::exclusive(from)), Some(Bound::inclusive(to)), Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut skipped = vec![];
+        for (counter, mut milestone) in crossed {
+            if milestone.achieved.is_none() {
+                milestone.achieved = Some(Achievement { donor: owner.clone(), height: env.block.height });
+                MILESTONES.save(storage, counter, &milestone)?;
+                skipped.push(Event::new("milestone_skipped").add_attribute("counter", counter.to_string()));
+            }
+        }
+
+        Ok(skipped)
+    }
+
+    /// Marks the milestones crossed when the counter goes from `from` to `to` as achieved by
+    /// `donor` and performs their actions. Bonuses are capped to the balance left after `outgoing`
+    /// funds are sent, and added to them.
+    fn reach_milestones(mut deps: DepsMut, env: &Env, donor: &Addr, from: u64, to: u64, outgoing: &mut Vec<Coin>)
+        -> Result<Vec<(Event, Option<CosmosMsg>)>, ContractError> {
+        let crossed = MILESTONES
+            .range(deps.storage, Some(Bound::exclusive(from)), Some(Bound::inclusive(to)), Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut reached = vec![];
+        for (counter, milestone) in crossed {
+            if milestone.achieved.is_none() {
+                reached.push(reach_milestone(deps.branch(), env, donor, counter, milestone, outgoing)?);
+            }
+        }
+
+        Ok(reached)
+    }
+
+    fn reach_milestone(deps: DepsMut, env: &Env, donor: &Addr, counter: u64, mut milestone: Milestone,
+                       outgoing: &mut Vec<Coin>) -> Result<(Event, Option<CosmosMsg>), ContractError> {
+        milestone.achieved = Some(Achievement { donor: donor.clone(), height: env.block.height });
+        MILESTONES.save(deps.storage, counter, &milestone)?;
+
+        let event = Event::new("milestone")
+            .add_attribute("counter", counter.to_string())
+            .add_attribute("donor", donor.as_str());
+
+        let msg: Option<CosmosMsg> = match milestone.action {
+            MilestoneAction::Bonus { amount } => {
+                let mut available = donation_balance(deps.as_ref(), env)?;
+                sub_coins(&mut available, outgoing);
+
+                let mut bonus = vec![];
+                for mut coin in amount {
+                    let available = available.iter()
+                        .find(|available| available.denom == coin.denom)
+                        .map(|available| available.amount)
+                        .unwrap_or_default();
+
+                    coin.amount = coin.amount.min(available);
+                    if !coin.amount.is_zero() {
+                        bonus.push(coin);
+                    }
+                }
+
+                add_coins(outgoing, &bonus);
+                (!bonus.is_empty()).then(|| BankMsg::Send { to_address: donor.to_string(), amount: bonus }.into())
+            }
+            MilestoneAction::Event {} => None,
+            MilestoneAction::Notify { contract, msg } => Some(WasmMsg::Execute {
+                contract_addr: contract,
+                msg,
+                funds: vec![],
+            }.into()),
+        };
+
+        Ok((event, msg))
+    }
+
+    /// Takes the protocol fee from the donated funds. Accrued fees are escrowed until the recipient
+    /// claims them, otherwise the message paying the recipient is returned.
+    fn charge_fee(storage: &mut dyn Storage, funds: &[Coin]) -> Result<(Vec<Coin>, Option<BankMsg>), ContractError> {
+        let fee = match FEE.may_load(storage)? {
+            Some(fee) => fee,
+            None => return Ok((vec![], None)),
+        };
+
+        let charged: Vec<Coin> = funds.iter()
+            .map(|funds| coin((funds.amount * fee.rate).u128(), &funds.denom))
+            .filter(|coin| !coin.amount.is_zero())
+            .collect();
+
+        if charged.is_empty() {
+            return Ok((vec![], None));
+        }
+
+        let mut collected = FEES_COLLECTED.may_load(storage)?.unwrap_or_default();
+        add_coins(&mut collected, &charged);
+        FEES_COLLECTED.save(storage, &collected)?;
+
+        let msg = match fee.mode {
+            FeeMode::Immediate {} => Some(BankMsg::Send { to_address: fee.recipient.into_string(), amount: charged.clone() }),
+            FeeMode::Accrued {} => {
+                let mut accrued = FEES_ACCRUED.may_load(storage)?.unwrap_or_default();
+                add_coins(&mut accrued, &charged);
+                FEES_ACCRUED.save(storage, &accrued)?;
+                None
+            }
+        };
+
+        Ok((charged, msg))
+    }
+
+    /// Matches the donation from the active matching pool, moving the matched amount from the escrow
+    /// to the donation balance.
+    fn match_donation(storage: &mut dyn Storage, env: &Env, donor: &Addr, funds: &[Coin])
+        -> Result<Option<Coin>, ContractError> {
+        let mut pool = match MATCHING_POOL.may_load(storage)? {
+            Some(pool) if env.block.time < pool.expires_at => pool,
+            _ => return Ok(None),
+        };
+
+        let donated = funds.iter()
+            .find(|coin| coin.denom == pool.remaining.denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+        let total = MATCHED.may_load(storage, (pool.id, donor))?.unwrap_or_default();
+
+        let mut matched = (donated * pool.ratio).min(pool.remaining.amount);
+        if let Some(cap) = pool.cap {
+            matched = matched.min(cap.saturating_sub(total));
+        }
+
+        if matched.is_zero() {
+            return Ok(None);
+        }
+
+        pool.remaining.amount -= matched;
+        MATCHING_POOL.save(storage, &pool)?;
+        MATCHED.save(storage, (pool.id, donor), &(total + matched))?;
+
+        Ok(Some(coin(matched.u128(), pool.remaining.denom)))
+    }
+
+    /// Records the donation in the running campaign and escrows it.
+    fn contribute_to_campaign(storage: &mut dyn Storage, env: &Env, donor: &Addr, funds: &[Coin])
+        -> Result<Option<u64>, ContractError> {
+        let id = match ACTIVE_CAMPAIGN.may_load(storage)? {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let mut campaign = CAMPAIGNS.load(storage, id)?;
+        if env.block.time < campaign.start || env.block.time >= campaign.end || funds.is_empty() {
+            return Ok(None);
+        }
+
+        add_coins(&mut campaign.raised, funds);
+        CAMPAIGNS.save(storage, id, &campaign)?;
+
+        let mut contribution = CONTRIBUTIONS.may_load(storage, (id, donor))?.unwrap_or_default();
+        add_coins(&mut contribution, funds);
+        CONTRIBUTIONS.save(storage, (id, donor), &contribution)?;
+
+        let mut escrow = CAMPAIGN_ESCROW.may_load(storage)?.unwrap_or_default();
+        add_coins(&mut escrow, funds);
+        CAMPAIGN_ESCROW.save(storage, &escrow)?;
+
+        Ok(Some(id))
+    }
+
+    fn mint_receipt(storage: &mut dyn Storage, env: &Env, info: &MessageInfo, counter: u64, config: ReceiptConfig)
+        -> Result<(u64, Option<WasmMsg>), ContractError> {
+        let id = NEXT_RECEIPT_ID.may_load(storage)?.unwrap_or_default();
+        NEXT_RECEIPT_ID.save(storage, &(id + 1))?;
+
+        let receipt = Receipt {
+            owner: info.sender.clone(),
+            counter,
+            amount: info.funds.clone(),
+            time: env.block.time,
+        };
+        RECEIPTS.save(storage, id, &receipt)?;
+        RECEIPTS_BY_OWNER.save(storage, (&info.sender, id), &Empty {})?;
+
+        let msg = match config {
+            ReceiptConfig::Internal => None,
+            ReceiptConfig::External { nft_contract } => Some(WasmMsg::Execute {
+                contract_addr: nft_contract.into_string(),
+                msg: to_binary(&Cw721ExecMsg::Mint {
+                    token_id: id.to_string(),
+                    owner: receipt.owner.into_string(),
+                    token_uri: None,
+                    extension: ReceiptExtension { counter, amount: receipt.amount, time: receipt.time },
+                })?,
+                funds: vec![],
+            }),
+        };
+
+        Ok((id, msg))
+    }
+
+    pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo, amount: Option<Vec<Coin>>) -> Result<Response, ContractError> {
+        let owner = ensure_owner(deps.as_ref(), &info.sender)?;
+        let (_, withdrawable) = vested(deps.as_ref(), &env)?;
+
+        let funds: Vec<Coin> = match amount {
+            None => withdrawable,
+            Some(amount) => {
+                for requested in &amount {
+                    let available = withdrawable.iter()
+                        .find(|coin| coin.denom == requested.denom)
+                        .map(|coin| coin.amount)
+                        .unwrap_or_default();
+
+                    if requested.amount > available {
+                        return Err(ContractError::ExceedsVested { available: coin(available.u128(), &requested.denom) });
+                    }
+                }
+
+                amount.into_iter().filter(|coin| !coin.amount.is_zero()).collect()
+            }
+        };
+
+        if funds.is_empty() {
+            return Err(ContractError::NothingToWithdraw);
+        }
+
+        let mut withdrawn = WITHDRAWN.may_load(deps.storage)?.unwrap_or_default();
+        add_coins(&mut withdrawn, &funds);
+        WITHDRAWN.save(deps.storage, &withdrawn)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "withdraw")
+            .add_attribute("sender", info.sender.as_str());
+
+        let funds = match SWAP.may_load(deps.storage)?.filter(|config| config.trigger == SwapTrigger::Withdraw {}) {
+            Some(config) => {
+                let (msgs, kept) = swap_coins(deps, &config, funds, Some(&owner))?;
+                resp = resp.add_submessages(msgs);
+                kept
+            }
+            None => funds,
+        };
+
+        if !funds.is_empty() {
+            resp = resp.add_message(BankMsg::Send { to_address: owner.to_string(), amount: funds });
+        }
+
+        Ok(resp)
+    }
+
+    pub fn update_parent(deps: DepsMut, env: Env, info: MessageInfo, parent: Option<Parent>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        let register = super::update_parent(deps, &env, parent)?;
+
+        let resp = Response::new()
+            .add_messages(register)
+            .add_attribute("action", "update_parent")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn update_minimal_donation(deps: DepsMut, info: MessageInfo, minimal_donation: Coin) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        validation::minimal_donation(&minimal_donation)?;
+
+        let mut state = STATE.load(deps.storage, DEFAULT_COUNTER)?;
+        state.minimal_donation = minimal_donation;
+        STATE.save(deps.storage, DEFAULT_COUNTER, &state)?;
+        // A pending change would otherwise override this one once its height is reached
+        SCHEDULED_MINIMAL_DONATION.remove(deps.storage);
+
+        let resp = Response::new()
+            .add_attribute("action", "update_minimal_donation")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("minimal_donation", state.minimal_donation.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn set_oracle(deps: DepsMut, info: MessageInfo, config: Option<OracleConfig>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        match config {
+            Some(config) => {
+                validation::oracle(deps.as_ref(), &config)?;
+                ORACLE.save(deps.storage, &config)?;
+            }
+            None => ORACLE.remove(deps.storage),
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_oracle")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn set_swap(deps: DepsMut, info: MessageInfo, config: Option<SwapConfig>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        match config {
+            Some(config) => {
+                validation::swap(deps.as_ref(), &config)?;
+                SWAP.save(deps.storage, &config)?;
+            }
+            None => SWAP.remove(deps.storage),
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_swap")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn schedule_minimal_donation(deps: DepsMut, env: Env, info: MessageInfo, minimal_donation: Coin, height: u64)
+        -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        validation::minimal_donation(&minimal_donation)?;
+
+        if height <= env.block.height {
+            return Err(ContractError::InvalidScheduleHeight { height });
+        }
+
+        SCHEDULED_MINIMAL_DONATION.save(deps.storage, &ScheduledMinimalDonation { minimal_donation, height })?;
+
+        let resp = Response::new()
+            .add_attribute("action", "schedule_minimal_donation")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("height", height.to_string());
+
+        Ok(resp)
+    }
+
+    /// Runs the admin messages with the existing handlers. Attributes of every message are grouped
+    /// in its own `admin_msg` event.
+    pub fn batch(mut deps: DepsMut, env: Env, info: MessageInfo, msgs: Vec<AdminMsg>) -> Result<Response, ContractError> {
+        const MAX_BATCH_SIZE: usize = 10;
+
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        if msgs.len() > MAX_BATCH_SIZE {
+            return Err(ContractError::BatchTooLarge { max: MAX_BATCH_SIZE as u32 });
+        }
+
+        // Every withdrawal is computed from the balance before any of them is paid out
+        if msgs.iter().filter(|msg| matches!(msg, AdminMsg::Withdraw { .. })).count() > 1 {
+            return Err(ContractError::BatchWithdrawals);
+        }
+
+        let mut resp = Response::new()
+            .add_attribute("action", "batch")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("size", msgs.len().to_string());
+
+        for (index, msg) in msgs.into_iter().enumerate() {
+            let (deps, env, info) = (deps.branch(), env.clone(), info.clone());
+
+            let sub = match msg {
+                AdminMsg::UpdateParent { parent } => update_parent(deps, env, info, parent)?,
+                AdminMsg::UpdateMinimalDonation { minimal_donation } =>
+                    update_minimal_donation(deps, info, minimal_donation)?,
+                AdminMsg::ScheduleMinimalDonation { minimal_donation, height } =>
+                    schedule_minimal_donation(deps, env, info, minimal_donation, height)?,
+                AdminMsg::Withdraw { amount } => withdraw(deps, env, info, amount)?,
+                AdminMsg::SetReceiptMode { mode } => set_receipt_mode(deps, info, mode)?,
+                AdminMsg::AddMilestone { counter, action } => add_milestone(deps, info, counter, action)?,
+                AdminMsg::RemoveMilestone { counter } => remove_milestone(deps, info, counter)?,
+                AdminMsg::SetDonorMode { mode } => set_donor_mode(deps, info, mode)?,
+                AdminMsg::SetChildApproval { child, approved } => set_child_approval(deps, info, child, approved)?,
+                AdminMsg::UpdateAllowlist { add, remove } => update_allowlist(deps, info, add, remove)?,
+                AdminMsg::UpdateDenylist { add, remove } => update_denylist(deps, info, add, remove)?,
+                AdminMsg::SetVoucherSigner { signer } => set_voucher_signer(deps, info, signer)?,
+                AdminMsg::SetHistoryRetention { blocks } => set_history_retention(deps, info, blocks)?,
+                AdminMsg::SetGovernance { config } => set_governance(deps, info, config)?,
+                AdminMsg::SetIbcParent { parent } => set_ibc_parent(deps, info, parent)?,
+                AdminMsg::UpdateIbcAllowlist { add, remove } => update_ibc_allowlist(deps, info, add, remove)?,
+                AdminMsg::SetOracle { config } => set_oracle(deps, info, config)?,
+                AdminMsg::SetSwap { config } => set_swap(deps, info, config)?,
+            };
+
+            let event = Event::new("admin_msg")
+                .add_attribute("index", index.to_string())
+                .add_attributes(sub.attributes);
+
+            resp = resp
+                .add_submessages(sub.messages)
+                .add_events(sub.events)
+                .add_event(event);
+        }
+
+        Ok(resp)
+    }
+
+    pub fn register_child(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+        let parent = deps.querier
+            .query_wasm_smart::<ParentResp>(&info.sender, &QueryMsg::Parent {})
+            .ok()
+            .and_then(|resp| resp.parent);
+
+        if parent.map(|parent| parent.addr) != Some(env.contract.address.into_string()) {
+            return Err(ContractError::NotAChild { addr: info.sender.into_string() });
+        }
+
+        CHILDREN.update(deps.storage, &info.sender, |child| -> StdResult<_> {
+            Ok(child.unwrap_or_default())
+        })?;
+
+        let resp = Response::new()
+            .add_attribute("action", "register_child")
+            .add_attribute("child", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn set_child_approval(deps: DepsMut, info: MessageInfo, child: String, approved: bool)
+        -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        let child = deps.api.addr_validate(&child)?;
+
+        let mut child_info = CHILDREN.may_load(deps.storage, &child)?
+            .ok_or_else(|| ContractError::NotAChild { addr: child.to_string() })?;
+        child_info.approved = approved;
+        CHILDREN.save(deps.storage, &child, &child_info)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_child_approval")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("child", child.as_str())
+            .add_attribute("approved", approved.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn set_receipt_mode(deps: DepsMut, info: MessageInfo, mode: Option<ReceiptMode>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        match mode {
+            Some(ReceiptMode::Internal {}) => RECEIPT_CONFIG.save(deps.storage, &ReceiptConfig::Internal)?,
+            Some(ReceiptMode::External { nft_contract }) => {
+                let nft_contract = deps.api.addr_validate(&nft_contract)?;
+                RECEIPT_CONFIG.save(deps.storage, &ReceiptConfig::External { nft_contract })?
+            }
+            None => RECEIPT_CONFIG.remove(deps.storage),
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_receipt_mode")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn set_donor_mode(deps: DepsMut, info: MessageInfo, mode: DonorMode) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        DONOR_MODE.save(deps.storage, &mode)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_donor_mode")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn update_allowlist(deps: DepsMut, info: MessageInfo, add: Vec<String>, remove: Vec<String>)
+        -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        let add = validate_addrs(deps.api, add)?;
+        let remove = validate_addrs(deps.api, remove)?;
+        update_list(deps.storage, &ALLOWLIST, &add, &remove)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "update_allowlist")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn update_denylist(deps: DepsMut, info: MessageInfo, add: Vec<String>, remove: Vec<String>)
+        -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        let add = validate_addrs(deps.api, add)?;
+        let remove = validate_addrs(deps.api, remove)?;
+        update_list(deps.storage, &DENYLIST, &add, &remove)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "update_denylist")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn update_ibc_allowlist(deps: DepsMut, info: MessageInfo, add: Vec<IbcCounterparty>, remove: Vec<IbcCounterparty>)
+        -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        for counterparty in &add {
+            IBC_ALLOWLIST.save(deps.storage, (&counterparty.connection_id, &counterparty.port_id), &Empty {})?;
+        }
+
+        for counterparty in &remove {
+            IBC_ALLOWLIST.remove(deps.storage, (&counterparty.connection_id, &counterparty.port_id));
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "update_ibc_allowlist")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    fn validate_addrs(api: &dyn Api, addrs: Vec<String>) -> StdResult<Vec<Addr>> {
+        addrs.iter().map(|addr| api.addr_validate(addr)).collect()
+    }
+
+    fn update_list<'a>(storage: &mut dyn Storage, list: &Map<&'a Addr, Empty>, add: &'a [Addr], remove: &'a [Addr])
+        -> StdResult<()> {
+        for addr in add {
+            list.save(storage, addr, &Empty {})?;
+        }
+
+        for addr in remove {
+            list.remove(storage, addr);
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_counter(deps: DepsMut, env: Env, info: MessageInfo, id: String, counter: u64, minimal_donation: Coin,
+                          owner: Option<String>, parent: Option<Parent>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        validation::counter_id(&id)?;
+        validation::minimal_donation(&minimal_donation)?;
+
+        if STATE.has(deps.storage, &id) {
+            return Err(ContractError::CounterExists { id });
+        }
+
+        let owner = match owner {
+            Some(owner) => deps.api.addr_validate(&owner)?,
+            None => info.sender.clone(),
+        };
+
+        let donating_parent = match parent {
+            Some(parent) => {
+                let max_depth = max_parent_depth(deps.as_ref())?;
+                let parent = validation::parent(deps.as_ref(), &env, parent, max_depth)?;
+                PARENT_DONATION.save(deps.storage, &id, &parent)?;
+                Some(parent.donating_parent_period)
+            }
+            None => None,
+        };
+
+        let state = State { counter, minimal_donation, donating_parent, owner: Some(owner) };
+        save_counter(deps.storage, &env, &id, &state)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "create_counter")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter_id", id);
+
+        Ok(resp)
+    }
+
+    pub fn set_history_retention(deps: DepsMut, info: MessageInfo, blocks: Option<u64>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        match blocks {
+            Some(blocks) => HISTORY_RETENTION.save(deps.storage, &blocks)?,
+            None => HISTORY_RETENTION.remove(deps.storage),
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_history_retention")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn prune_history(deps: DepsMut, env: Env, info: MessageInfo, counter_id: Option<String>, limit: Option<u32>)
+        -> Result<Response, ContractError> {
+        const DEFAULT_LIMIT: u32 = 30;
+        const MAX_LIMIT: u32 = 100;
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let counter_id = counter_id.unwrap_or_else(|| DEFAULT_COUNTER.to_owned());
+        let pruned = super::prune_history(deps.storage, &env, &counter_id, limit)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "prune_history")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("counter_id", counter_id)
+            .add_attribute("pruned", pruned.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn set_governance(deps: DepsMut, info: MessageInfo, config: Option<GovernanceConfig>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        match config {
+            Some(config) => {
+                validation::governance(&config)?;
+                GOVERNANCE.save(deps.storage, &config)?;
+            }
+            None => GOVERNANCE.remove(deps.storage),
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_governance")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn propose(deps: DepsMut, env: Env, info: MessageInfo, action: ProposalAction) -> Result<Response, ContractError> {
+        let config = GOVERNANCE.may_load(deps.storage)?.ok_or(ContractError::GovernanceDisabled)?;
+
+        if VOTING_WEIGHTS.may_load(deps.storage, &info.sender)?.unwrap_or_default().is_zero() {
+            return Err(ContractError::NoVotingWeight);
+        }
+
+        validation::proposal_action(deps.as_ref(), &action)?;
+
+        let id = NEXT_PROPOSAL_ID.may_load(deps.storage)?.unwrap_or_default();
+        NEXT_PROPOSAL_ID.save(deps.storage, &(id + 1))?;
+
+        let proposal = Proposal {
+            proposer: info.sender.clone(),
+            action,
+            end: env.block.time.plus_seconds(config.voting_period),
+            total_weight: total_weight_at(deps.storage, env.block.height)?,
+            start_height: env.block.height,
+            quorum: config.quorum,
+            threshold: config.threshold,
+            yes: Uint128::zero(),
+            no: Uint128::zero(),
+            executed: false,
+        };
+        PROPOSALS.save(deps.storage, id, &proposal)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "propose")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("proposal_id", id.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn vote(deps: DepsMut, env: Env, info: MessageInfo, proposal_id: u64, option: VoteOption) -> Result<Response, ContractError> {
+        let mut proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+        if env.block.time >= proposal.end {
+            return Err(ContractError::VotingClosed { id: proposal_id });
+        }
+
+        if BALLOTS.has(deps.storage, (proposal_id, &info.sender)) {
+            return Err(ContractError::AlreadyVoted { id: proposal_id });
+        }
+
+        // Weights donated after the proposal was created don't count
+        let weight = voting_weight_at(deps.storage, &info.sender, proposal.start_height)?;
+        if weight.is_zero() {
+            return Err(ContractError::NoVotingWeight);
+        }
+
+        match option {
+            VoteOption::Yes => proposal.yes += weight,
+            VoteOption::No => proposal.no += weight,
+        }
+        PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+        BALLOTS.save(deps.storage, (proposal_id, &info.sender), &Ballot { option, weight })?;
+
+        let resp = Response::new()
+            .add_attribute("action", "vote")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("proposal_id", proposal_id.to_string())
+            .add_attribute("weight", weight.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn execute_proposal(deps: DepsMut, env: Env, info: MessageInfo, proposal_id: u64) -> Result<Response, ContractError> {
+        let mut proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+
+        match proposal_status(&proposal, &env) {
+            ProposalStatus::Passed => (),
+            ProposalStatus::Open => return Err(ContractError::VotingNotEnded { end: proposal.end }),
+            _ => return Err(ContractError::ProposalNotPassed { id: proposal_id }),
+        }
+
+        proposal.executed = true;
+        PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+        let msg = WasmMsg::Execute {
+            contract_addr: env.contract.address.into_string(),
+            msg: to_binary(&ExecMsg::ApplyProposal { action: proposal.action })?,
+            funds: vec![],
+        };
+
+        let resp = Response::new()
+            .add_message(msg)
+            .add_attribute("action", "execute_proposal")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("proposal_id", proposal_id.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn apply_proposal(mut deps: DepsMut, env: Env, info: MessageInfo, action: ProposalAction) -> Result<Response, ContractError> {
+        if info.sender != env.contract.address {
+            return Err(ContractError::Unauthorized { owner: env.contract.address.into_string() });
+        }
+
+        let mut resp = Response::new()
+            .add_attribute("action", "apply_proposal")
+            .add_attribute("sender", info.sender.as_str());
+
+        match action {
+            ProposalAction::UpdateMinimalDonation { minimal_donation } => {
+                validation::minimal_donation(&minimal_donation)?;
+                let mut state = STATE.load(deps.storage, DEFAULT_COUNTER)?;
+                state.minimal_donation = minimal_donation;
+                STATE.save(deps.storage, DEFAULT_COUNTER, &state)?;
+                SCHEDULED_MINIMAL_DONATION.remove(deps.storage);
+            }
+            ProposalAction::UpdateParent { parent } => {
+                resp = resp.add_messages(super::update_parent(deps.branch(), &env, parent)?);
+            }
+            ProposalAction::UpdateOwner { owner } => {
+                let owner = deps.api.addr_validate(&owner)?;
+                OWNER.save(deps.storage, &owner)?;
+            }
+        }
+
+        Ok(resp)
+    }
+
+    pub fn set_ibc_parent(deps: DepsMut, info: MessageInfo, parent: Option<IbcParent>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        match parent {
+            Some(parent) => {
+                validation::ibc_parent(deps.as_ref(), &parent)?;
+                IBC_PARENT.save(deps.storage, &parent)?;
+            }
+            None => IBC_PARENT.remove(deps.storage),
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_ibc_parent")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn set_voucher_signer(deps: DepsMut, info: MessageInfo, signer: Option<VoucherSigner>) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        match signer {
+            Some(signer) => VOUCHER_SIGNER.save(deps.storage, &signer)?,
+            None => VOUCHER_SIGNER.remove(deps.storage),
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_voucher_signer")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn redeem_voucher(mut deps: DepsMut, env: Env, info: MessageInfo, voucher: Voucher, signature: Binary)
+        -> Result<Response, ContractError> {
+        if !info.funds.is_empty() {
+            return Err(ContractError::FundsNotAccepted);
+        }
+
+        let signer = VOUCHER_SIGNER.may_load(deps.storage)?.ok_or(ContractError::NoVoucherSigner)?;
+
+        if voucher.contract != env.contract.address {
+            return Err(ContractError::InvalidVoucherContract { contract: voucher.contract });
+        }
+
+        if env.block.time >= voucher.expires_at {
+            return Err(ContractError::VoucherExpired { expires_at: voucher.expires_at });
+        }
+
+        let donor = deps.api.addr_validate(&voucher.donor)?;
+        if VOUCHER_NONCES.has(deps.storage, (&donor, voucher.nonce)) {
+            return Err(ContractError::VoucherUsed { nonce: voucher.nonce });
+        }
+
+        // Malformed keys and signatures are reported the same way as the mismatching ones
+        let payload = to_vec(&voucher)?;
+        let valid = match signer {
+            VoucherSigner::Secp256k1 { pubkey } =>
+                deps.api.secp256k1_verify(&Sha256::digest(&payload), &signature, &pubkey),
+            VoucherSigner::Ed25519 { pubkey } => deps.api.ed25519_verify(&payload, &signature, &pubkey),
+        }.unwrap_or(false);
+
+        if !valid {
+            return Err(ContractError::InvalidSignature);
+        }
+
+        ensure_donor_allowed(deps.as_ref(), &donor)?;
+        VOUCHER_NONCES.save(deps.storage, (&donor, voucher.nonce), &Empty {})?;
+
+        if !voucher.credit.is_empty() {
+            let mut credits = CREDITS.may_load(deps.storage, &donor)?.unwrap_or_default();
+            add_coins(&mut credits, &voucher.credit);
+            CREDITS.save(deps.storage, &donor, &credits)?;
+        }
+
+        let mut state = STATE.load(deps.storage, DEFAULT_COUNTER)?;
+        state.counter += 1;
+        save_counter(deps.storage, &env, DEFAULT_COUNTER, &state)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "redeem_voucher")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("donor", donor.as_str())
+            .add_attribute("counter", state.counter.to_string());
+
+        for (event, msg) in reach_milestones(deps.branch(), &env, &donor, state.counter - 1, state.counter, &mut vec![])? {
+            resp = resp.add_event(event).add_messages(msg);
+        }
+
+        Ok(resp)
+    }
+
+    pub fn add_milestone(deps: DepsMut, info: MessageInfo, counter: u64, action: MilestoneAction) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+        validation::milestone_action(deps.as_ref(), &action)?;
+
+        if counter <= STATE.load(deps.storage, DEFAULT_COUNTER)?.counter {
+            return Err(ContractError::InvalidMilestone { counter });
+        }
+
+        MILESTONES.save(deps.storage, counter, &Milestone { action, achieved: None })?;
+
+        let resp = Response::new()
+            .add_attribute("action", "add_milestone")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("milestone", counter.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn remove_milestone(deps: DepsMut, info: MessageInfo, counter: u64) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        MILESTONES.remove(deps.storage, counter);
+
+        let resp = Response::new()
+            .add_attribute("action", "remove_milestone")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("milestone", counter.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn fund_matching_pool(deps: DepsMut, env: Env, info: MessageInfo, ratio: Decimal, cap: Option<Uint128>,
+                              expires_at: Timestamp) -> Result<Response, ContractError> {
+        if MATCHING_POOL.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::MatchingPoolExists);
+        }
+
+        let funds = match info.funds.as_slice() {
+            [funds] if !funds.amount.is_zero() => funds.clone(),
+            _ => return Err(ContractError::InvalidMatchingFunds),
+        };
+
+        if expires_at <= env.block.time {
+            return Err(ContractError::InvalidExpiry);
+        }
+
+        let id = NEXT_MATCHING_POOL_ID.may_load(deps.storage)?.unwrap_or_default();
+        NEXT_MATCHING_POOL_ID.save(deps.storage, &(id + 1))?;
+
+        MATCHING_POOL.save(deps.storage, &MatchingPool {
+            id,
+            sponsor: info.sender.clone(),
+            remaining: funds,
+            ratio,
+            cap,
+            expires_at,
+        })?;
+
+        let resp = Response::new()
+            .add_attribute("action", "fund_matching_pool")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("pool_id", id.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn reclaim_matching_pool(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+        let pool = MATCHING_POOL.may_load(deps.storage)?.ok_or(ContractError::NoMatchingPool)?;
+
+        if info.sender != pool.sponsor {
+            return Err(ContractError::Unauthorized { owner: pool.sponsor.into_string() });
+        }
+
+        if env.block.time < pool.expires_at {
+            return Err(ContractError::MatchingPoolActive { expires_at: pool.expires_at });
+        }
+
+        MATCHING_POOL.remove(deps.storage);
+
+        let mut resp = Response::new()
+            .add_attribute("action", "reclaim_matching_pool")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("reclaimed", pool.remaining.to_string());
+
+        if !pool.remaining.amount.is_zero() {
+            resp = resp.add_message(BankMsg::Send {
+                to_address: pool.sponsor.into_string(),
+                amount: vec![pool.remaining],
+            });
+        }
+
+        Ok(resp)
+    }
+
+    pub fn start_campaign(deps: DepsMut, env: Env, info: MessageInfo, start: Option<Timestamp>, end: Timestamp,
+                          goal: Vec<Coin>, release: CampaignRelease) -> Result<Response, ContractError> {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+
+        if let Some(id) = ACTIVE_CAMPAIGN.may_load(deps.storage)? {
+            return Err(ContractError::CampaignActive { id });
+        }
+
+        let start = start.unwrap_or(env.block.time);
+        if end <= start || end <= env.block.time {
+            return Err(ContractError::InvalidCampaignTime);
+        }
+
+        for coin in &goal {
+            validation::denom(&coin.denom)?;
+        }
+
+        if release == (CampaignRelease::Parent {}) && PARENT_DONATION.may_load(deps.storage, DEFAULT_COUNTER)?.is_none() {
+            return Err(ContractError::NoParent);
+        }
+
+        let id = NEXT_CAMPAIGN_ID.may_load(deps.storage)?.unwrap_or_default();
+        NEXT_CAMPAIGN_ID.save(deps.storage, &(id + 1))?;
+        ACTIVE_CAMPAIGN.save(deps.storage, &id)?;
+
+        CAMPAIGNS.save(deps.storage, id, &Campaign {
+            start,
+            end,
+            goal,
+            raised: vec![],
+            release,
+            status: CampaignStatus::Active,
+            refund_pool: vec![],
+        })?;
+
+        let resp = Response::new()
+            .add_attribute("action", "start_campaign")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("campaign", id.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn finalize_campaign(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+        let id = ACTIVE_CAMPAIGN.may_load(deps.storage)?.ok_or(ContractError::NoActiveCampaign)?;
+        let mut campaign = CAMPAIGNS.load(deps.storage, id)?;
+
+        if env.block.time < campaign.end {
+            return Err(ContractError::CampaignNotEnded { end: campaign.end });
+        }
+
+        ACTIVE_CAMPAIGN.remove(deps.storage);
+
+        let succeeded = campaign.goal.iter().all(|goal| {
+            campaign.raised.iter().any(|raised| raised.denom == goal.denom && raised.amount >= goal.amount)
+        });
+
+        let mut escrow = CAMPAIGN_ESCROW.may_load(deps.storage)?.unwrap_or_default();
+        sub_coins(&mut escrow, &campaign.raised);
+
+        let mut resp = Response::new()
+            .add_attribute("action", "finalize_campaign")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("campaign", id.to_string());
+
+        if succeeded {
+            campaign.status = CampaignStatus::Succeeded;
+
+            if !campaign.raised.is_empty() {
+                let msg: CosmosMsg = match campaign.release {
+                    CampaignRelease::Owner {} => BankMsg::Send {
+                        to_address: OWNER.load(deps.storage)?.into_string(),
+                        amount: campaign.raised.clone(),
+                    }.into(),
+                    CampaignRelease::Parent {} => {
+                        let parent = PARENT_DONATION.load(deps.storage, DEFAULT_COUNTER)?.address;
+                        donate_to_parent(deps.as_ref(), &parent, 1, campaign.raised.clone())?.into()
+                    }
+                };
+                resp = resp.add_message(msg);
+            }
+        } else {
+            campaign.status = CampaignStatus::Failed;
+
+            // Refunds are paid out of what the contract actually holds, split pro-rata
+            let balance = deps.querier.query_all_balances(&env.contract.address)?;
+            campaign.refund_pool = campaign.raised.iter()
+                .map(|raised| {
+                    let available = balance.iter()
+                        .find(|coin| coin.denom == raised.denom)
+                        .map(|coin| coin.amount)
+                        .unwrap_or_default();
+                    coin(raised.amount.min(available).u128(), &raised.denom)
+                })
+                .collect();
+
+            add_coins(&mut escrow, &campaign.refund_pool);
+        }
+
+        CAMPAIGN_ESCROW.save(deps.storage, &escrow)?;
+        CAMPAIGNS.save(deps.storage, id, &campaign)?;
+
+        Ok(resp.add_attribute("succeeded", succeeded.to_string()))
+    }
+
+    pub fn claim_refund(deps: DepsMut, info: MessageInfo, campaign_id: u64) -> Result<Response, ContractError> {
+        let campaign = CAMPAIGNS.load(deps.storage, campaign_id)?;
+        if campaign.status != CampaignStatus::Failed {
+            return Err(ContractError::CampaignNotFailed { id: campaign_id });
+        }
+
+        let contribution = CONTRIBUTIONS
+            .may_load(deps.storage, (campaign_id, &info.sender))?
+            .ok_or(ContractError::NothingToRefund)?;
+        CONTRIBUTIONS.remove(deps.storage, (campaign_id, &info.sender));
+
+        let refund: Vec<Coin> = contribution.into_iter()
+            .filter_map(|contributed| {
+                let amount_of = |coins: &[Coin]| coins.iter()
+                    .find(|coin| coin.denom == contributed.denom)
+                    .map(|coin| coin.amount)
+                    .unwrap_or_default();
+
+                let raised = amount_of(&campaign.raised);
+                let amount = contributed.amount.multiply_ratio(amount_of(&campaign.refund_pool), raised);
+                (!amount.is_zero()).then(|| coin(amount.u128(), contributed.denom))
+            })
+            .collect();
+
+        let mut escrow = CAMPAIGN_ESCROW.may_load(deps.storage)?.unwrap_or_default();
+        sub_coins(&mut escrow, &refund);
+        CAMPAIGN_ESCROW.save(deps.storage, &escrow)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "claim_refund")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("campaign", campaign_id.to_string());
+
+        if !refund.is_empty() {
+            resp = resp.add_message(BankMsg::Send { to_address: info.sender.into_string(), amount: refund });
+        }
+
+        Ok(resp)
+    }
+
+    pub fn claim_fees(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        let fee = FEE.may_load(deps.storage)?.ok_or(ContractError::NothingToClaim)?;
+        if info.sender != fee.recipient {
+            return Err(ContractError::Unauthorized { owner: fee.recipient.into_string() });
+        }
+
+        let accrued = FEES_ACCRUED.may_load(deps.storage)?.unwrap_or_default();
+        if accrued.is_empty() {
+            return Err(ContractError::NothingToClaim);
+        }
+        FEES_ACCRUED.remove(deps.storage);
+
+        let resp = Response::new()
+            .add_message(BankMsg::Send { to_address: fee.recipient.into_string(), amount: accrued })
+            .add_attribute("action", "claim_fees")
+            .add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn claim_parent_share(deps: DepsMut, info: MessageInfo, parent: Option<String>) -> Result<Response, ContractError> {
+        let parent = match parent {
+            Some(parent) => deps.api.addr_validate(&parent)?,
+            None => info.sender.clone(),
+        };
+
+        let share = PARENT_SHARES.may_load(deps.storage, &parent)?.ok_or(ContractError::NothingToClaim)?;
+        PARENT_SHARES.remove(deps.storage, &parent);
+
+        let mut escrow = PARENT_SHARE_ESCROW.may_load(deps.storage)?.unwrap_or_default();
+        sub_coins(&mut escrow, &share);
+        PARENT_SHARE_ESCROW.save(deps.storage, &escrow)?;
+
+        // Donated like pushed shares, so the parent counts it and tracks this contract as its child
+        let msg = donate_to_parent(deps.as_ref(), &parent, 1, share.clone())?;
+
+        let resp = Response::new()
+            .add_message(msg)
+            .add_attribute("action", "claim_parent_share")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("parent", parent.as_str())
+            .add_attribute("amount", coins_to_string(&share));
+
+        Ok(resp)
+    }
+
+    pub fn subscribe(deps: DepsMut, env: Env, info: MessageInfo, counter_id: Option<String>, amount: Vec<Coin>, interval: u64)
+        -> Result<Response, ContractError> {
+        let counter_id = counter_id.unwrap_or_else(|| DEFAULT_COUNTER.to_owned());
+        load_counter(deps.storage, &counter_id)?;
+        ensure_donor_allowed(deps.as_ref(), &info.sender)?;
+
+        let mut instalment = vec![];
+        add_coins(&mut instalment, &amount);
+        instalment.retain(|coin| !coin.amount.is_zero());
+
+        if interval == 0 || instalment.is_empty() || !covers(&info.funds, &instalment) {
+            return Err(ContractError::InvalidSubscription);
+        }
+
+        let id = NEXT_SUBSCRIPTION_ID.may_load(deps.storage)?.unwrap_or_default();
+        NEXT_SUBSCRIPTION_ID.save(deps.storage, &(id + 1))?;
+
+        let subscription = Subscription {
+            donor: info.sender.clone(),
+            counter_id,
+            amount: instalment,
+            interval,
+            next_due: env.block.time,
+            remaining: info.funds.clone(),
+        };
+        SUBSCRIPTIONS.save(deps.storage, id, &subscription)?;
+        SUBSCRIPTIONS_BY_DONOR.save(deps.storage, (&info.sender, id), &Empty {})?;
+        SUBSCRIPTIONS_BY_DUE.save(deps.storage, (subscription.next_due.nanos(), id), &Empty {})?;
+
+        let mut escrow = SUBSCRIPTION_ESCROW.may_load(deps.storage)?.unwrap_or_default();
+        add_coins(&mut escrow, &info.funds);
+        SUBSCRIPTION_ESCROW.save(deps.storage, &escrow)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "subscribe")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("id", id.to_string());
+
+        Ok(resp)
+    }
+
+    /// Each instalment is paid in its own `PayInstalment` submessage, so a failing one is reverted on
+    /// its own and its subscription ended in the reply. Subscriptions of donors who are no longer
+    /// accepted are ended and refunded right away.
+    pub fn process_subscriptions(deps: DepsMut, env: Env, info: MessageInfo, limit: Option<u32>)
+        -> Result<Response, ContractError> {
+        const DEFAULT_LIMIT: u32 = 10;
+        const MAX_LIMIT: u32 = 30;
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let due = SUBSCRIPTIONS_BY_DUE
+            .keys(deps.storage, None, Some(Bound::inclusive((env.block.time.nanos(), u64::MAX))), Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut dispatched = 0;
+        let mut resp = Response::new()
+            .add_attribute("action", "process_subscriptions")
+            .add_attribute("sender", info.sender.as_str());
+
+        for (_, id) in due {
+            let subscription = SUBSCRIPTIONS.load(deps.storage, id)?;
+
+            if ensure_donor_allowed(deps.as_ref(), &subscription.donor).is_err() {
+                let event = Event::new("subscription_instalment")
+                    .add_attribute("id", id.to_string())
+                    .add_attribute("ended", "donor_not_allowed");
+                resp = resp
+                    .add_messages(end_subscription(deps.storage, id, &subscription)?)
+                    .add_event(event);
+                continue;
+            }
+
+            let pay = WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecMsg::PayInstalment { id })?,
+                funds: vec![],
+            };
+            resp = resp.add_submessage(SubMsg::reply_on_error(pay, id | INSTALMENT_REPLY_FLAG));
+            dispatched += 1;
+        }
+
+        Ok(resp.add_attribute("dispatched", dispatched.to_string()))
+    }
+
+    /// The instalment goes through `donate` as if the donor sent it, its attributes are reported in
+    /// a `subscription_instalment` event.
+    pub fn pay_instalment(mut deps: DepsMut, env: Env, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+        if info.sender != env.contract.address {
+            return Err(ContractError::Unauthorized { owner: env.contract.address.into_string() });
+        }
+
+        let mut subscription = SUBSCRIPTIONS.load(deps.storage, id)?;
+        SUBSCRIPTIONS_BY_DUE.remove(deps.storage, (subscription.next_due.nanos(), id));
+
+        // Released from the escrow so the instalment is part of the balance, like sent funds
+        let mut escrow = SUBSCRIPTION_ESCROW.may_load(deps.storage)?.unwrap_or_default();
+        sub_coins(&mut escrow, &subscription.amount);
+        SUBSCRIPTION_ESCROW.save(deps.storage, &escrow)?;
+        sub_coins(&mut subscription.remaining, &subscription.amount);
+
+        let instalment = MessageInfo { sender: subscription.donor.clone(), funds: subscription.amount.clone() };
+        let donated = donate(deps.branch(), env, instalment, None, Some(subscription.counter_id.clone()))?;
+        let mut event = Event::new("subscription_instalment")
+            .add_attribute("id", id.to_string())
+            .add_attributes(donated.attributes);
+        let mut resp = Response::new()
+            .add_attribute("action", "pay_instalment")
+            .add_attribute("id", id.to_string())
+            .add_submessages(donated.messages)
+            .add_events(donated.events);
+
+        if covers(&subscription.remaining, &subscription.amount) {
+            subscription.next_due = subscription.next_due.plus_seconds(subscription.interval);
+            SUBSCRIPTIONS.save(deps.storage, id, &subscription)?;
+            SUBSCRIPTIONS_BY_DUE.save(deps.storage, (subscription.next_due.nanos(), id), &Empty {})?;
+        } else {
+            resp = resp.add_messages(end_subscription(deps.storage, id, &subscription)?);
+            event = event.add_attribute("ended", "funds_used");
+        }
+
+        Ok(resp.add_event(event))
+    }
+
+    /// Ends the subscription whose instalment failed, refunding what is left of it.
+    pub fn end_failed_instalment(deps: DepsMut, id: u64, error: String) -> Result<Response, ContractError> {
+        let subscription = SUBSCRIPTIONS.load(deps.storage, id)?;
+
+        let event = Event::new("subscription_instalment")
+            .add_attribute("id", id.to_string())
+            .add_attribute("ended", "donation_failed")
+            .add_attribute("error", error);
+
+        let resp = Response::new()
+            .add_messages(end_subscription(deps.storage, id, &subscription)?)
+            .add_event(event);
+
+        Ok(resp)
+    }
+
+    pub fn cancel_subscription(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+        let subscription = SUBSCRIPTIONS.load(deps.storage, id)?;
+        if info.sender != subscription.donor {
+            return Err(ContractError::Unauthorized { owner: subscription.donor.into_string() });
+        }
+
+        let resp = Response::new()
+            .add_messages(end_subscription(deps.storage, id, &subscription)?)
+            .add_attribute("action", "cancel_subscription")
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("id", id.to_string());
+
+        Ok(resp)
+    }
+
+    /// Whether `funds` hold at least `amount` of every denom.
+    fn covers(funds: &[Coin], amount: &[Coin]) -> bool {
+        amount.iter().all(|needed| funds.iter().any(|coin| coin.denom == needed.denom && coin.amount >= needed.amount))
+    }
+
+    /// Removes the subscription and takes its remaining funds out of the escrow, returning the
+    /// refund to the donor.
+    fn end_subscription(storage: &mut dyn Storage, id: u64, subscription: &Subscription) -> StdResult<Option<BankMsg>> {
+        SUBSCRIPTIONS.remove(storage, id);
+        SUBSCRIPTIONS_BY_DONOR.remove(storage, (&subscription.donor, id));
+        SUBSCRIPTIONS_BY_DUE.remove(storage, (subscription.next_due.nanos(), id));
+
+        let mut escrow = SUBSCRIPTION_ESCROW.may_load(storage)?.unwrap_or_default();
+        sub_coins(&mut escrow, &subscription.remaining);
+        SUBSCRIPTION_ESCROW.save(storage, &escrow)?;
+
+        Ok((!subscription.remaining.is_empty()).then(|| BankMsg::Send {
+            to_address: subscription.donor.to_string(),
+            amount: subscription.remaining.clone(),
+        }))
+    }
+}
+
+pub mod sudo {
+    use cosmwasm_std::{BankMsg, DepsMut, Env, Response};
+    use crate::error::ContractError;
+    use crate::msg::Parent;
+    use crate::state::{DEFAULT_COUNTER, OWNER, STATE, WITHDRAWN};
+    use super::{add_coins, donation_balance, save_counter};
+
+    pub fn set_owner(deps: DepsMut, owner: String) -> Result<Response, ContractError> {
+        let owner = deps.api.addr_validate(&owner)?;
+        OWNER.save(deps.storage, &owner)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "sudo_set_owner")
+            .add_attribute("owner", owner.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn force_reset(deps: DepsMut, env: Env, counter: u64, counter_id: Option<String>) -> Result<Response, ContractError> {
+        let counter_id = counter_id.unwrap_or_else(|| DEFAULT_COUNTER.to_owned());
+        let mut state = STATE.may_load(deps.storage, &counter_id)?
+            .ok_or_else(|| ContractError::UnknownCounter { id: counter_id.clone() })?;
+
+        state.counter = counter;
+        save_counter(deps.storage, &env, &counter_id, &state)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "sudo_force_reset")
+            .add_attribute("counter_id", &counter_id)
+            .add_attribute("counter", counter.to_string());
+
+        Ok(resp)
+    }
+
+    /// Withdrawn funds are recorded, so the vesting schedule continues from what is left.
+    pub fn force_withdraw(deps: DepsMut, env: Env, to: String) -> Result<Response, ContractError> {
+        let to = deps.api.addr_validate(&to)?;
+
+        let funds = donation_balance(deps.as_ref(), &env)?;
+        if funds.is_empty() {
+            return Err(ContractError::NothingToWithdraw);
+        }
+
+        let mut withdrawn = WITHDRAWN.may_load(deps.storage)?.unwrap_or_default();
+        add_coins(&mut withdrawn, &funds);
+        WITHDRAWN.save(deps.storage, &withdrawn)?;
+
+        let resp = Response::new()
+            .add_message(BankMsg::Send { to_address: to.to_string(), amount: funds })
+            .add_attribute("action", "sudo_force_withdraw")
+            .add_attribute("to", to.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn update_parent(deps: DepsMut, env: Env, parent: Option<Parent>) -> Result<Response, ContractError> {
+        let register = super::update_parent(deps, &env, parent)?;
+
+        let resp = Response::new()
+            .add_messages(register)
+            .add_attribute("action", "sudo_update_parent");
+
+        Ok(resp)
+    }
+}
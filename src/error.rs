@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, Decimal, StdError, Timestamp};
 use thiserror::Error;
 
 
@@ -18,4 +18,226 @@ pub enum ContractError {
     #[error("Migrating from unsupported version: {0}")]
     InvalidVersion(String),
 
+    #[error("Donating period must be greater than zero")]
+    InvalidPeriod,
+
+    #[error("Part donated to parent must not exceed 1, got {0}")]
+    InvalidPart(Decimal),
+
+    #[error("Contract cannot be its own parent")]
+    SelfParent,
+
+    #[error("Invalid denom: {0}")]
+    InvalidDenom(String),
+
+    #[error("Parent chain loops back to this contract")]
+    ParentCycle,
+
+    #[error("Parent chain is deeper than {max_depth} contracts")]
+    ParentDepthExceeded {
+        max_depth: u32,
+    },
+
+    #[error("{addr} is not configured to donate to this contract")]
+    NotAChild {
+        addr: String,
+    },
+
+    #[error("Milestone {counter} is not above the current counter")]
+    InvalidMilestone {
+        counter: u64,
+    },
+
+    #[error("Matching pool has to be funded with exactly one non-zero coin")]
+    InvalidMatchingFunds,
+
+    #[error("Matching pool already exists")]
+    MatchingPoolExists,
+
+    #[error("No matching pool")]
+    NoMatchingPool,
+
+    #[error("Matching pool is active until {expires_at}")]
+    MatchingPoolActive {
+        expires_at: Timestamp,
+    },
+
+    #[error("Expiration time has already passed")]
+    InvalidExpiry,
+
+    #[error("Campaign {id} is still active")]
+    CampaignActive {
+        id: u64,
+    },
+
+    #[error("No active campaign")]
+    NoActiveCampaign,
+
+    #[error("Campaign lasts until {end}")]
+    CampaignNotEnded {
+        end: Timestamp,
+    },
+
+    #[error("Campaign has to end in the future and after it starts")]
+    InvalidCampaignTime,
+
+    #[error("Campaign {id} did not fail")]
+    CampaignNotFailed {
+        id: u64,
+    },
+
+    #[error("Nothing to refund")]
+    NothingToRefund,
+
+    #[error("No parent configured")]
+    NoParent,
+
+    #[error("Vesting has to end after it starts")]
+    InvalidVesting,
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw,
+
+    #[error("Only {available} can be withdrawn")]
+    ExceedsVested {
+        available: Coin,
+    },
+
+    #[error("Invalid fee rate: {0}")]
+    InvalidFeeRate(Decimal),
+
+    #[error("Nothing to claim")]
+    NothingToClaim,
+
+    #[error("Donations from {donor} are not accepted")]
+    DonorNotAllowed {
+        donor: String,
+    },
+
+    #[error("Vouchers are not enabled")]
+    NoVoucherSigner,
+
+    #[error("Voucher is issued for {contract}")]
+    InvalidVoucherContract {
+        contract: String,
+    },
+
+    #[error("Voucher expired at {expires_at}")]
+    VoucherExpired {
+        expires_at: Timestamp,
+    },
+
+    #[error("Voucher nonce {nonce} was already used")]
+    VoucherUsed {
+        nonce: u64,
+    },
+
+    #[error("Invalid voucher signature")]
+    InvalidSignature,
+
+    #[error("No funds can be sent with this message")]
+    FundsNotAccepted,
+
+    #[error("Invalid counter id: {0}")]
+    InvalidCounterId(String),
+
+    #[error("Counter {id} already exists")]
+    CounterExists {
+        id: String,
+    },
+
+    #[error("Counter {id} does not exist")]
+    UnknownCounter {
+        id: String,
+    },
+
+    #[error("Governance is not enabled")]
+    GovernanceDisabled,
+
+    #[error("Quorum has to be positive and at most one, threshold lower than one")]
+    InvalidGovernanceConfig,
+
+    #[error("Only donors can propose and vote")]
+    NoVotingWeight,
+
+    #[error("Voting on proposal {id} is closed")]
+    VotingClosed {
+        id: u64,
+    },
+
+    #[error("Already voted on proposal {id}")]
+    AlreadyVoted {
+        id: u64,
+    },
+
+    #[error("Voting is open until {end}")]
+    VotingNotEnded {
+        end: Timestamp,
+    },
+
+    #[error("Proposal {id} did not pass or was already executed")]
+    ProposalNotPassed {
+        id: u64,
+    },
+
+    #[error("Only unordered channels are supported")]
+    InvalidIbcOrder,
+
+    #[error("Invalid IBC version: {version}")]
+    InvalidIbcVersion {
+        version: String,
+    },
+
+    #[error("Channel {channel_id} is not connected")]
+    UnknownChannel {
+        channel_id: String,
+    },
+
+    #[error("Channels with port {port_id} over {connection_id} are not allowed")]
+    IbcCounterpartyNotAllowed {
+        connection_id: String,
+        port_id: String,
+    },
+
+    #[error("IBC timeout must be greater than zero")]
+    InvalidIbcTimeout,
+
+    #[error("Scheduled height {height} is not in the future")]
+    InvalidScheduleHeight {
+        height: u64,
+    },
+
+    #[error("Oracle has to price at least one denom")]
+    NoOracleDenoms,
+
+    #[error("Price of {denom} was last updated at {updated_at}")]
+    StalePrice {
+        denom: String,
+        updated_at: Timestamp,
+    },
+
+    #[error("Oracle prices can't be older than {max} seconds")]
+    InvalidOracleMaxAge {
+        max: u64,
+    },
+
+    #[error("Value of the donated {denom} overflows")]
+    ValueOverflow {
+        denom: String,
+    },
+
+    #[error("Slippage must not exceed 1, got {0}")]
+    InvalidSlippage(Decimal),
+
+    #[error("Subscription needs a positive interval and amount, and funds covering its first instalment")]
+    InvalidSubscription,
+
+    #[error("Batch can contain at most {max} messages")]
+    BatchTooLarge {
+        max: u32,
+    },
+
+    #[error("Batch can contain at most one withdrawal")]
+    BatchWithdrawals,
+
 }
\ No newline at end of file
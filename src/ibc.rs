@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests;
+
+use cosmwasm_std::{Coin, DepsMut, Env, from_binary, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+                   IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcMsg, IbcOrder, IbcPacket,
+                   IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout, StdResult,
+                   Storage, to_binary};
+use crate::contract::{activate_minimal_donation, add_coins, coins_to_string, meets_minimal_donation, save_counter,
+                      sub_coins};
+use crate::error::ContractError;
+use crate::msg::{IbcAck, IbcPacketMsg, IbcParent};
+use crate::state::{DEFAULT_COUNTER, IBC_ALLOWLIST, IBC_CHANNELS, IBC_ESCROW, IBC_FORWARDS, IBC_PARENT, IbcChannelInfo,
+                   IbcForward, NEXT_IBC_FORWARD_ID, STATE};
+
+/// Version of the channels between counting contracts
+pub const IBC_VERSION: &str = "counting-1";
+
+fn validate_channel(storage: &dyn Storage, channel: &IbcChannel, counterparty_version: Option<&str>)
+    -> Result<(), ContractError> {
+    let port_id = &channel.counterparty_endpoint.port_id;
+    if !IBC_ALLOWLIST.has(storage, (&channel.connection_id, port_id)) {
+        return Err(ContractError::IbcCounterpartyNotAllowed {
+            connection_id: channel.connection_id.clone(),
+            port_id: port_id.clone(),
+        });
+    }
+
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::InvalidIbcOrder);
+    }
+
+    if channel.version != IBC_VERSION {
+        return Err(ContractError::InvalidIbcVersion { version: channel.version.clone() });
+    }
+
+    if let Some(version) = counterparty_version {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidIbcVersion { version: version.to_owned() });
+        }
+    }
+
+    Ok(())
+}
+
+pub fn channel_open(deps: DepsMut, _env: Env, msg: IbcChannelOpenMsg) -> Result<IbcChannelOpenResponse, ContractError> {
+    validate_channel(deps.storage, msg.channel(), msg.counterparty_version())?;
+    Ok(None)
+}
+
+pub fn channel_connect(deps: DepsMut, _env: Env, msg: IbcChannelConnectMsg) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(deps.storage, channel, msg.counterparty_version())?;
+
+    let info = IbcChannelInfo {
+        counterparty: channel.counterparty_endpoint.clone(),
+        received: vec![],
+    };
+    IBC_CHANNELS.save(deps.storage, &channel.endpoint.channel_id, &info)?;
+
+    let resp = IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id);
+
+    Ok(resp)
+}
+
+/// Forwards still waiting on the closed channel are refunded once their notices time out.
+pub fn channel_close(deps: DepsMut, _env: Env, msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    IBC_CHANNELS.remove(deps.storage, channel_id);
+
+    if let Some(parent) = IBC_PARENT.may_load(deps.storage)? {
+        if parent.channel_id == *channel_id {
+            IBC_PARENT.remove(deps.storage);
+        }
+    }
+
+    let resp = IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", channel_id);
+
+    Ok(resp)
+}
+
+/// Failures are acknowledged with an error instead of failing the transaction, so the child can
+/// release the escrowed funds.
+pub fn packet_receive(deps: DepsMut, env: Env, msg: IbcPacketReceiveMsg) -> Result<IbcReceiveResponse, ContractError> {
+    match receive_donation(deps, &env, &msg.packet) {
+        Ok(resp) => Ok(resp),
+        Err(err) => {
+            let resp = IbcReceiveResponse::new()
+                .set_ack(to_binary(&IbcAck::Error { error: err.to_string() })?)
+                .add_attribute("action", "ibc_receive_donation")
+                .add_attribute("error", err.to_string());
+
+            Ok(resp)
+        }
+    }
+}
+
+/// Counts a donation notified by a child on another chain if the notified amount reaches the
+/// minimal donation of the default counter, as local donations do. The funds arrive separately
+/// with an ICS-20 transfer. The oracle only prices local denoms, so it is not used here.
+fn receive_donation(deps: DepsMut, env: &Env, packet: &IbcPacket) -> Result<IbcReceiveResponse, ContractError> {
+    let channel_id = &packet.dest.channel_id;
+    let mut channel = IBC_CHANNELS.may_load(deps.storage, channel_id)?
+        .ok_or_else(|| ContractError::UnknownChannel { channel_id: channel_id.clone() })?;
+    let IbcPacketMsg::Donation { amount, .. } = from_binary(&packet.data)?;
+
+    let mut state = STATE.load(deps.storage, DEFAULT_COUNTER)?;
+    let activated = activate_minimal_donation(deps.storage, env, &mut state)?;
+    if meets_minimal_donation(&state.minimal_donation, &amount) {
+        state.counter += 1;
+        save_counter(deps.storage, env, DEFAULT_COUNTER, &state)?;
+    }
+
+    add_coins(&mut channel.received, &amount);
+    IBC_CHANNELS.save(deps.storage, channel_id, &channel)?;
+
+    let resp = IbcReceiveResponse::new()
+        .set_ack(to_binary(&IbcAck::Ok {})?)
+        .add_events(activated)
+        .add_attribute("action", "ibc_receive_donation")
+        .add_attribute("channel_id", channel_id)
+        .add_attribute("counter", state.counter.to_string());
+
+    Ok(resp)
+}
+
+/// Transfers the escrowed funds once the parent counted the donation, releases them otherwise.
+pub fn packet_ack(deps: DepsMut, env: Env, msg: IbcPacketAckMsg) -> Result<IbcBasicResponse, ContractError> {
+    let IbcPacketMsg::Donation { forward_id, .. } = from_binary(&msg.original_packet.data)?;
+    let ack: IbcAck = from_binary(&msg.acknowledgement.data)?;
+    let forward = release_forward(deps.storage, forward_id)?;
+
+    let resp = IbcBasicResponse::new()
+        .add_attribute("action", "ibc_packet_ack")
+        .add_attribute("forward_id", forward_id.to_string());
+
+    let resp = match ack {
+        IbcAck::Ok {} => {
+            let timeout = IbcTimeout::with_timestamp(env.block.time.plus_seconds(forward.timeout));
+            let transfers = forward.amount.into_iter()
+                .map(|amount| IbcMsg::Transfer {
+                    channel_id: forward.transfer_channel_id.clone(),
+                    to_address: forward.address.clone(),
+                    amount,
+                    timeout: timeout.clone(),
+                });
+
+            resp.add_messages(transfers)
+        }
+        IbcAck::Error { error } => resp.add_attribute("error", error),
+    };
+
+    Ok(resp)
+}
+
+pub fn packet_timeout(deps: DepsMut, _env: Env, msg: IbcPacketTimeoutMsg) -> Result<IbcBasicResponse, ContractError> {
+    let IbcPacketMsg::Donation { forward_id, .. } = from_binary(&msg.packet.data)?;
+    let forward = release_forward(deps.storage, forward_id)?;
+
+    let resp = IbcBasicResponse::new()
+        .add_attribute("action", "ibc_packet_timeout")
+        .add_attribute("forward_id", forward_id.to_string())
+        .add_attribute("refunded", coins_to_string(&forward.amount));
+
+    Ok(resp)
+}
+
+/// Escrows `amount` and returns the notice for the parent. The funds are transferred when the
+/// parent acknowledges it.
+pub fn forward_donation(storage: &mut dyn Storage, env: &Env, parent: &IbcParent, amount: Vec<Coin>) -> StdResult<IbcMsg> {
+    let id = NEXT_IBC_FORWARD_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_IBC_FORWARD_ID.save(storage, &(id + 1))?;
+
+    let mut escrow = IBC_ESCROW.may_load(storage)?.unwrap_or_default();
+    add_coins(&mut escrow, &amount);
+    IBC_ESCROW.save(storage, &escrow)?;
+
+    let data = to_binary(&IbcPacketMsg::Donation { forward_id: id, amount: amount.clone() })?;
+
+    let forward = IbcForward {
+        transfer_channel_id: parent.transfer_channel_id.clone(),
+        address: parent.addr.clone(),
+        amount,
+        timeout: parent.timeout,
+    };
+    IBC_FORWARDS.save(storage, id, &forward)?;
+
+    Ok(IbcMsg::SendPacket {
+        channel_id: parent.channel_id.clone(),
+        data,
+        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(parent.timeout)),
+    })
+}
+
+/// Removes the pending forward and takes its funds out of the escrow.
+fn release_forward(storage: &mut dyn Storage, id: u64) -> StdResult<IbcForward> {
+    let forward = IBC_FORWARDS.load(storage, id)?;
+    IBC_FORWARDS.remove(storage, id);
+
+    let mut escrow = IBC_ESCROW.may_load(storage)?.unwrap_or_default();
+    sub_coins(&mut escrow, &forward.amount);
+    IBC_ESCROW.save(storage, &escrow)?;
+
+    Ok(forward)
+}
@@ -0,0 +1,226 @@
+use cosmwasm_std::{Coin, coins, CosmosMsg, Decimal, from_binary, IbcAcknowledgement, IbcMsg, IbcOrder, IbcTimeout,
+                   OwnedDeps, to_binary};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_ibc_channel_close_init, mock_ibc_channel_connect_ack,
+                            mock_ibc_channel_open_try, mock_ibc_packet_ack, mock_ibc_packet_recv,
+                            mock_ibc_packet_timeout, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use crate::{execute, ibc_channel_close, ibc_channel_connect, ibc_channel_open, ibc_packet_ack, ibc_packet_receive,
+            ibc_packet_timeout, instantiate, query, ContractError};
+use crate::msg::{ConfigResp, ExecMsg, IbcAck, IbcAllowlistResp, IbcChannelsResp, IbcCounterparty, IbcPacketMsg, IbcParent,
+                 InstantiateMsg, QueryMsg, ValueResp};
+use crate::state::{IBC_ESCROW, IBC_FORWARDS};
+use super::IBC_VERSION;
+
+const ATOM: &str = "atom";
+const CHANNEL: &str = "channel-0";
+const TRANSFER_CHANNEL: &str = "channel-1";
+
+type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+fn instantiate_contract() -> MockDeps {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        counter: 0,
+        minimal_donation: Coin::new(10, ATOM),
+        parent: None,
+        max_parent_depth: None,
+        vesting: None,
+        fee: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    deps
+}
+
+/// Counterparty of the mocked channels
+fn counterparty() -> IbcCounterparty {
+    IbcCounterparty { connection_id: "connection-2".to_owned(), port_id: "their_port".to_owned() }
+}
+
+fn setup() -> MockDeps {
+    let mut deps = instantiate_contract();
+
+    let msg = ExecMsg::UpdateIbcAllowlist { add: vec![counterparty()], remove: vec![] };
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    let open = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap();
+    let connect = mock_ibc_channel_connect_ack(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap();
+
+    deps
+}
+
+fn ibc_parent() -> IbcParent {
+    IbcParent {
+        channel_id: CHANNEL.to_owned(),
+        transfer_channel_id: TRANSFER_CHANNEL.to_owned(),
+        addr: "remote-parent".to_owned(),
+        part: Decimal::percent(50),
+        timeout: 60,
+    }
+}
+
+/// Donates `amount` atoms, returning the notice sent to the parent
+fn donate(deps: &mut MockDeps, amount: u128) -> IbcMsg {
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(amount, ATOM));
+
+    let msg = ExecMsg::Donate { depth: None, counter_id: None };
+    let resp = execute(deps.as_mut(), mock_env(), mock_info("donor", &coins(amount, ATOM)), msg).unwrap();
+
+    assert_eq!(resp.messages.len(), 1);
+    match resp.messages[0].msg.clone() {
+        CosmosMsg::Ibc(msg) => msg,
+        msg => panic!("unexpected message: {:?}", msg),
+    }
+}
+
+#[test]
+fn channel_handshake() {
+    let mut deps = instantiate_contract();
+
+    let open = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap_err();
+    assert_eq!(err, ContractError::IbcCounterpartyNotAllowed {
+        connection_id: "connection-2".to_owned(),
+        port_id: "their_port".to_owned(),
+    });
+
+    let msg = ExecMsg::UpdateIbcAllowlist { add: vec![counterparty()], remove: vec![] };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("donor", &[]), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized { owner: "owner".to_owned() });
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    let resp: IbcAllowlistResp = from_binary(
+        &query(deps.as_ref(), mock_env(), QueryMsg::IbcAllowlist { start_after: None, limit: None }).unwrap()
+    ).unwrap();
+    assert_eq!(resp.counterparties, vec![counterparty()]);
+
+    let open = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Ordered, IBC_VERSION);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap_err();
+    assert_eq!(err, ContractError::InvalidIbcOrder);
+
+    let open = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Unordered, "ics20-1");
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap_err();
+    assert_eq!(err, ContractError::InvalidIbcVersion { version: "ics20-1".to_owned() });
+
+    let mut deps = setup();
+    let resp: IbcChannelsResp = from_binary(
+        &query(deps.as_ref(), mock_env(), QueryMsg::IbcChannels { start_after: None, limit: None }).unwrap()
+    ).unwrap();
+    assert_eq!(resp.channels.len(), 1);
+    assert_eq!(resp.channels[0].channel_id, CHANNEL);
+
+    // Removed counterparties can't open new channels
+    let msg = ExecMsg::UpdateIbcAllowlist { add: vec![], remove: vec![counterparty()] };
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    let connect = mock_ibc_channel_connect_ack("channel-3", IbcOrder::Unordered, IBC_VERSION);
+    let err = ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap_err();
+    assert_eq!(err, ContractError::IbcCounterpartyNotAllowed {
+        connection_id: "connection-2".to_owned(),
+        port_id: "their_port".to_owned(),
+    });
+}
+
+#[test]
+fn forward_lifecycle() {
+    let mut deps = setup();
+
+    let mut parent = ibc_parent();
+    parent.channel_id = "channel-7".to_owned();
+    let msg = ExecMsg::SetIbcParent { parent: Some(parent) };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::UnknownChannel { channel_id: "channel-7".to_owned() });
+
+    let msg = ExecMsg::SetIbcParent { parent: Some(ibc_parent()) };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("donor", &[]), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized { owner: "owner".to_owned() });
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    // Half of the donation is escrowed and notified to the parent
+    let notice = IbcPacketMsg::Donation { forward_id: 0, amount: coins(5, ATOM) };
+    let timeout = IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(60));
+    assert_eq!(donate(&mut deps, 10), IbcMsg::SendPacket {
+        channel_id: CHANNEL.to_owned(),
+        data: to_binary(&notice).unwrap(),
+        timeout: timeout.clone(),
+    });
+    assert_eq!(IBC_ESCROW.load(&deps.storage).unwrap(), coins(5, ATOM));
+
+    // The funds are transferred once the parent counts the donation
+    let ack = IbcAcknowledgement::new(to_binary(&IbcAck::Ok {}).unwrap());
+    let msg = mock_ibc_packet_ack(CHANNEL, &notice, ack).unwrap();
+    let resp = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(resp.messages[0].msg, IbcMsg::Transfer {
+        channel_id: TRANSFER_CHANNEL.to_owned(),
+        to_address: "remote-parent".to_owned(),
+        amount: Coin::new(5, ATOM),
+        timeout,
+    }.into());
+    assert_eq!(IBC_ESCROW.load(&deps.storage).unwrap(), vec![]);
+    assert!(!IBC_FORWARDS.has(&deps.storage, 0));
+
+    // Timed out notices release the funds back to the donation balance
+    donate(&mut deps, 20);
+    let notice = IbcPacketMsg::Donation { forward_id: 1, amount: coins(10, ATOM) };
+    assert_eq!(IBC_ESCROW.load(&deps.storage).unwrap(), coins(10, ATOM));
+
+    let msg = mock_ibc_packet_timeout(CHANNEL, &notice).unwrap();
+    let resp = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(resp.messages.is_empty());
+    assert_eq!(IBC_ESCROW.load(&deps.storage).unwrap(), vec![]);
+
+    // So do rejected ones
+    donate(&mut deps, 10);
+    let notice = IbcPacketMsg::Donation { forward_id: 2, amount: coins(5, ATOM) };
+    let ack = IbcAcknowledgement::new(to_binary(&IbcAck::Error { error: "rejected".to_owned() }).unwrap());
+    let msg = mock_ibc_packet_ack(CHANNEL, &notice, ack).unwrap();
+    let resp = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(resp.messages.is_empty());
+    assert_eq!(IBC_ESCROW.load(&deps.storage).unwrap(), vec![]);
+
+    // Closing the channel drops the parent
+    let close = mock_ibc_channel_close_init(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_close(deps.as_mut(), mock_env(), close).unwrap();
+    let config: ConfigResp = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.ibc_parent, None);
+}
+
+#[test]
+fn receive_donation() {
+    let mut deps = setup();
+
+    let notice = IbcPacketMsg::Donation { forward_id: 3, amount: coins(10, ATOM) };
+    let msg = mock_ibc_packet_recv(CHANNEL, &notice).unwrap();
+    let resp = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(from_binary::<IbcAck>(&resp.acknowledgement).unwrap(), IbcAck::Ok {});
+
+    let value: ValueResp = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Value {}).unwrap()).unwrap();
+    assert_eq!(value, ValueResp { value: 1 });
+
+    // Notices below the minimal donation are acknowledged, but not counted
+    for amount in [coins(5, ATOM), coins(5, "uosmo")] {
+        let notice = IbcPacketMsg::Donation { forward_id: 4, amount };
+        let msg = mock_ibc_packet_recv(CHANNEL, &notice).unwrap();
+        let resp = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(from_binary::<IbcAck>(&resp.acknowledgement).unwrap(), IbcAck::Ok {});
+    }
+
+    let value: ValueResp = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Value {}).unwrap()).unwrap();
+    assert_eq!(value, ValueResp { value: 1 });
+
+    let resp: IbcChannelsResp = from_binary(
+        &query(deps.as_ref(), mock_env(), QueryMsg::IbcChannels { start_after: None, limit: None }).unwrap()
+    ).unwrap();
+    assert_eq!(resp.channels[0].received, vec![Coin::new(15, ATOM), Coin::new(5, "uosmo")]);
+
+    // Notices over unknown channels are rejected with an error acknowledgement
+    let msg = mock_ibc_packet_recv("channel-9", &notice).unwrap();
+    let resp = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(from_binary::<IbcAck>(&resp.acknowledgement).unwrap(), IbcAck::Error {
+        error: ContractError::UnknownChannel { channel_id: "channel-9".to_owned() }.to_string(),
+    });
+
+    let value: ValueResp = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Value {}).unwrap()).unwrap();
+    assert_eq!(value, ValueResp { value: 1 });
+}
@@ -1,46 +1,171 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, StdResult, Binary, Deps, to_binary, Empty};
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Reply, Response, StdResult, Binary, Deps, to_binary, IbcBasicResponse,
+                   IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcPacketAckMsg,
+                   IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse};
 use crate::error::ContractError;
-use crate::msg::{ExecMsg, InstantiateMsg, MigrateMsg};
+use crate::msg::{ExecMsg, InstantiateMsg, MigrateMsg, SudoMsg};
 mod contract;
+mod ibc;
 pub mod msg;
 mod state;
+mod validation;
 pub mod error;
 #[cfg(any(test, feature = "tests"))]
 pub mod multitest;
 
 #[allow(dead_code)]
 #[cfg_attr(not(feature = "library"), entry_point)]
-fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
-    contract::instantiate(deps, info, msg)
+fn instantiate(deps: DepsMut, env: Env, info: MessageInfo, msg: InstantiateMsg) -> Result<Response, ContractError> {
+    contract::instantiate(deps, env, info, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
     use msg::QueryMsg::*;
     use contract::query;
 
     match msg {
         Value {} => to_binary(&query::value(deps)?),
-        Incremented { value } => to_binary(&query::incremented(value))
+        Incremented { value } => to_binary(&query::incremented(value)),
+        Parent {} => to_binary(&query::parent(deps)?),
+        Children { start_after, limit } => to_binary(&query::children(deps, start_after, limit)?),
+        Ancestors {} => to_binary(&query::ancestors(deps)?),
+        Receipts { owner, start_after, limit } => to_binary(&query::receipts(deps, owner, start_after, limit)?),
+        Receipt { id } => to_binary(&query::receipt(deps, id)?),
+        UpcomingMilestones { start_after, limit } => to_binary(&query::milestones(deps, false, start_after, limit)?),
+        AchievedMilestones { start_after, limit } => to_binary(&query::milestones(deps, true, start_after, limit)?),
+        MatchingPool {} => to_binary(&query::matching_pool(deps)?),
+        Matched { pool_id, donor } => to_binary(&query::matched(deps, pool_id, donor)?),
+        Campaign { id } => to_binary(&query::campaign(deps, id)?),
+        CampaignContribution { campaign_id, donor } =>
+            to_binary(&query::campaign_contribution(deps, campaign_id, donor)?),
+        Vesting {} => to_binary(&query::vesting(deps, env)?),
+        Config {} => to_binary(&query::config(deps)?),
+        Allowlist { start_after, limit } => to_binary(&query::allowlist(deps, start_after, limit)?),
+        Denylist { start_after, limit } => to_binary(&query::denylist(deps, start_after, limit)?),
+        Credits { donor } => to_binary(&query::credits(deps, donor)?),
+        Counter { id } => to_binary(&query::counter(deps, id)?),
+        ListCounters { start_after, limit } => to_binary(&query::counters(deps, start_after, limit)?),
+        ValueAt { height, counter_id } => to_binary(&query::value_at(deps, height, counter_id)?),
+        ValueHistory { counter_id, start_after, limit } =>
+            to_binary(&query::value_history(deps, counter_id, start_after, limit)?),
+        Proposal { id } => to_binary(&query::proposal(deps, env, id)?),
+        Proposals { start_after, limit } => to_binary(&query::proposals(deps, env, start_after, limit)?),
+        Votes { proposal_id, start_after, limit } => to_binary(&query::votes(deps, proposal_id, start_after, limit)?),
+        VotingWeight { donor } => to_binary(&query::voting_weight(deps, donor)?),
+        MinimalDonation {} => to_binary(&query::minimal_donation(deps, env)?),
+        IbcChannels { start_after, limit } => to_binary(&query::ibc_channels(deps, start_after, limit)?),
+        IbcAllowlist { start_after, limit } => to_binary(&query::ibc_allowlist(deps, start_after, limit)?),
+        ParentShare { parent } => to_binary(&query::parent_share(deps, parent)?),
+        Subscription { id } => to_binary(&query::subscription(deps, id)?),
+        Subscriptions { donor, start_after, limit } =>
+            to_binary(&query::subscriptions(deps, donor, start_after, limit)?),
+        SubscriptionsByDue { start_after, limit } => to_binary(&query::subscriptions_by_due(deps, start_after, limit)?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: msg::ExecMsg) -> Result<Response, ContractError> {
     match msg {
-        ExecMsg::Donate {} => contract::exec::donate(deps, env, info).map_err(ContractError::Std),
-        ExecMsg::Reset { counter } => contract::exec::reset(deps, info, counter).map_err(ContractError::Std),
-        ExecMsg::Withdraw {} => contract::exec::withdraw(deps, env, info),
-    }?;
-    Ok(Response::new())
+        ExecMsg::Donate { depth, counter_id } => contract::exec::donate(deps, env, info, depth, counter_id),
+        ExecMsg::Reset { counter, counter_id } => contract::exec::reset(deps, env, info, counter, counter_id),
+        ExecMsg::Withdraw { amount } => contract::exec::withdraw(deps, env, info, amount),
+        ExecMsg::RegisterChild {} => contract::exec::register_child(deps, env, info),
+        ExecMsg::SetChildApproval { child, approved } => contract::exec::set_child_approval(deps, info, child, approved),
+        ExecMsg::SetReceiptMode { mode } => contract::exec::set_receipt_mode(deps, info, mode),
+        ExecMsg::AddMilestone { counter, action } => contract::exec::add_milestone(deps, info, counter, action),
+        ExecMsg::RemoveMilestone { counter } => contract::exec::remove_milestone(deps, info, counter),
+        ExecMsg::FundMatchingPool { ratio, cap, expires_at } =>
+            contract::exec::fund_matching_pool(deps, env, info, ratio, cap, expires_at),
+        ExecMsg::ReclaimMatchingPool {} => contract::exec::reclaim_matching_pool(deps, env, info),
+        ExecMsg::StartCampaign { start, end, goal, release } =>
+            contract::exec::start_campaign(deps, env, info, start, end, goal, release),
+        ExecMsg::FinalizeCampaign {} => contract::exec::finalize_campaign(deps, env, info),
+        ExecMsg::ClaimRefund { campaign_id } => contract::exec::claim_refund(deps, info, campaign_id),
+        ExecMsg::ClaimFees {} => contract::exec::claim_fees(deps, info),
+        ExecMsg::SetDonorMode { mode } => contract::exec::set_donor_mode(deps, info, mode),
+        ExecMsg::UpdateAllowlist { add, remove } => contract::exec::update_allowlist(deps, info, add, remove),
+        ExecMsg::UpdateDenylist { add, remove } => contract::exec::update_denylist(deps, info, add, remove),
+        ExecMsg::SetVoucherSigner { signer } => contract::exec::set_voucher_signer(deps, info, signer),
+        ExecMsg::CreateCounter { id, counter, minimal_donation, owner, parent } =>
+            contract::exec::create_counter(deps, env, info, id, counter, minimal_donation, owner, parent),
+        ExecMsg::SetHistoryRetention { blocks } => contract::exec::set_history_retention(deps, info, blocks),
+        ExecMsg::PruneHistory { counter_id, limit } => contract::exec::prune_history(deps, env, info, counter_id, limit),
+        ExecMsg::SetGovernance { config } => contract::exec::set_governance(deps, info, config),
+        ExecMsg::Propose { action } => contract::exec::propose(deps, env, info, action),
+        ExecMsg::Vote { proposal_id, option } => contract::exec::vote(deps, env, info, proposal_id, option),
+        ExecMsg::ExecuteProposal { proposal_id } => contract::exec::execute_proposal(deps, env, info, proposal_id),
+        ExecMsg::ApplyProposal { action } => contract::exec::apply_proposal(deps, env, info, action),
+        ExecMsg::RedeemVoucher { voucher, signature } =>
+            contract::exec::redeem_voucher(deps, env, info, voucher, signature),
+        ExecMsg::SetIbcParent { parent } => contract::exec::set_ibc_parent(deps, info, parent),
+        ExecMsg::UpdateIbcAllowlist { add, remove } => contract::exec::update_ibc_allowlist(deps, info, add, remove),
+        ExecMsg::UpdateMinimalDonation { minimal_donation } =>
+            contract::exec::update_minimal_donation(deps, info, minimal_donation),
+        ExecMsg::ScheduleMinimalDonation { minimal_donation, height } =>
+            contract::exec::schedule_minimal_donation(deps, env, info, minimal_donation, height),
+        ExecMsg::SetOracle { config } => contract::exec::set_oracle(deps, info, config),
+        ExecMsg::SetSwap { config } => contract::exec::set_swap(deps, info, config),
+        ExecMsg::ClaimParentShare { parent } => contract::exec::claim_parent_share(deps, info, parent),
+        ExecMsg::Subscribe { counter_id, amount, interval } =>
+            contract::exec::subscribe(deps, env, info, counter_id, amount, interval),
+        ExecMsg::ProcessSubscriptions { limit } => contract::exec::process_subscriptions(deps, env, info, limit),
+        ExecMsg::PayInstalment { id } => contract::exec::pay_instalment(deps, env, info, id),
+        ExecMsg::CancelSubscription { id } => contract::exec::cancel_subscription(deps, info, id),
+        ExecMsg::Batch { msgs } => contract::exec::batch(deps, env, info, msgs),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    contract::migrate(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    contract::reply(deps, env, reply)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::SetOwner { owner } => contract::sudo::set_owner(deps, owner),
+        SudoMsg::ForceReset { counter, counter_id } => contract::sudo::force_reset(deps, env, counter, counter_id),
+        SudoMsg::ForceWithdraw { to } => contract::sudo::force_withdraw(deps, env, to),
+        SudoMsg::UpdateParent { parent } => contract::sudo::update_parent(deps, env, parent),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(deps: DepsMut, env: Env, msg: IbcChannelOpenMsg) -> Result<IbcChannelOpenResponse, ContractError> {
+    ibc::channel_open(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(deps: DepsMut, env: Env, msg: IbcChannelConnectMsg) -> Result<IbcBasicResponse, ContractError> {
+    ibc::channel_connect(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(deps: DepsMut, env: Env, msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse, ContractError> {
+    ibc::channel_close(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(deps: DepsMut, env: Env, msg: IbcPacketReceiveMsg) -> Result<IbcReceiveResponse, ContractError> {
+    ibc::packet_receive(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(deps: DepsMut, env: Env, msg: IbcPacketAckMsg) -> Result<IbcBasicResponse, ContractError> {
+    ibc::packet_ack(deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    contract::migrate(deps, msg)
+pub fn ibc_packet_timeout(deps: DepsMut, env: Env, msg: IbcPacketTimeoutMsg) -> Result<IbcBasicResponse, ContractError> {
+    ibc::packet_timeout(deps, env, msg)
 }
 
 #[cfg(test)]
@@ -68,7 +193,7 @@ mod test {
         let contract_addr = app.instantiate_contract(
             contract_id,
             Addr::unchecked("sender"),
-            &InstantiateMsg { counter: 20 , minimal_donation: Coin::new(10, ATOM), parent: None },
+            &InstantiateMsg { counter: 20 , minimal_donation: Coin::new(10, ATOM), parent: None, max_parent_depth: None, vesting: None, fee: None },
             &[],
             "Counting contract",
             None
@@ -92,13 +217,13 @@ mod test {
         let contract_addr = app.instantiate_contract(
             contract_id,
             sender.clone(),
-            &InstantiateMsg { counter: 0, minimal_donation: Coin::new(10, ATOM), parent: None },
+            &InstantiateMsg { counter: 0, minimal_donation: Coin::new(10, ATOM), parent: None, max_parent_depth: None, vesting: None, fee: None },
             &[],
             "Counting contract",
             None
         ).unwrap();
 
-        app.execute_contract(sender, contract_addr.clone(), &ExecMsg::Donate {}, &[])
+        app.execute_contract(sender, contract_addr.clone(), &ExecMsg::Donate { depth: None, counter_id: None }, &[])
             .unwrap();
 
         let resp: ValueResp = app
@@ -169,7 +294,10 @@ mod test {
                 &InstantiateMsg {
                     counter: 0,
                     minimal_donation: Coin::new(0, ATOM),
-                    parent: None
+                    parent: None,
+                    max_parent_depth: None,
+                    vesting: None,
+                    fee: None,
                 },
                 &[],
                 "Counting contract",
@@ -180,7 +308,7 @@ mod test {
         app.execute_contract(
             Addr::unchecked("sender"),
             contract_addr.clone(),
-            &ExecMsg::Donate {},
+            &ExecMsg::Donate { depth: None, counter_id: None },
             &[],
         )
             .unwrap();
@@ -202,13 +330,13 @@ mod test {
         let contract_addr = app.instantiate_contract(
             contract_id,
             Addr::unchecked("sender"),
-            &InstantiateMsg { counter: 0, minimal_donation: Coin::new(10, ATOM), parent: None },
+            &InstantiateMsg { counter: 0, minimal_donation: Coin::new(10, ATOM), parent: None, max_parent_depth: None, vesting: None, fee: None },
             &[],
             "Counting contract",
             None
         ).unwrap();
 
-        app.execute_contract(Addr::unchecked("sender"), contract_addr.clone(), &ExecMsg::Reset { counter : 10}, &[])
+        app.execute_contract(Addr::unchecked("sender"), contract_addr.clone(), &ExecMsg::Reset { counter : 10, counter_id: None }, &[])
             .unwrap();
 
         let resp: ValueResp = app
@@ -228,7 +356,7 @@ mod test {
         let contract_addr = app.instantiate_contract(
             contract_id,
             Addr::unchecked("sender"),
-            &InstantiateMsg { counter: 0, minimal_donation: Coin::new(10, ATOM), parent: None },
+            &InstantiateMsg { counter: 0, minimal_donation: Coin::new(10, ATOM), parent: None, max_parent_depth: None, vesting: None, fee: None },
             &[],
             "Counting contract",
             None
@@ -312,7 +440,10 @@ mod test {
                 &InstantiateMsg {
                     counter: 0,
                     minimal_donation: coin(10, "atom"),
-                    parent: None
+                    parent: None,
+                    max_parent_depth: None,
+                    vesting: None,
+                    fee: None,
                 },
                 &[],
                 "Counting contract",
@@ -321,7 +452,7 @@ mod test {
             .unwrap();
 
         let err = app
-            .execute_contract(member,contract_addr.clone(),&ExecMsg::Withdraw {},&[])
+            .execute_contract(member,contract_addr.clone(),&ExecMsg::Withdraw { amount: None },&[])
             .unwrap_err();
 
         assert_eq!(ContractError::Unauthorized { owner: owner.into()}, err.downcast().unwrap())
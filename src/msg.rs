@@ -1,12 +1,47 @@
-use cosmwasm_std::{Coin, Decimal};
+use cosmwasm_std::{Binary, Coin, Decimal, Timestamp, Uint128};
 use cosmwasm_schema::{cw_serde, QueryResponses};
 
 
 #[cw_serde]
 pub struct Parent {
     pub addr: String,
+    /// Number of counted donations between forwards, only used in the period mode
     pub donating_period: u64,
     pub part: Decimal,
+    #[serde(default)]
+    pub mode: ForwardMode,
+    #[serde(default)]
+    pub payout: ParentPayout,
+}
+
+/// How donations are forwarded to the parent
+#[cw_serde]
+pub enum ForwardMode {
+    /// Every `donating_period` counted donations, `part` of the donation balance is forwarded
+    Period,
+    /// Every counted donation forwards `part` of the donated coins left after the fee
+    Stream,
+}
+
+impl Default for ForwardMode {
+    fn default() -> Self {
+        ForwardMode::Period
+    }
+}
+
+/// How forwarded funds reach the parent
+#[cw_serde]
+pub enum ParentPayout {
+    /// Sent to the parent within the donation forwarding them
+    Push,
+    /// Accrued in the contract until claimed with `ClaimParentShare`
+    Pull,
+}
+
+impl Default for ParentPayout {
+    fn default() -> Self {
+        ParentPayout::Push
+    }
 }
 
 #[cw_serde]
@@ -15,6 +50,40 @@ pub struct InstantiateMsg {
     pub counter: u64,
     pub minimal_donation: Coin,
     pub parent: Option<Parent>,
+    /// Maximal length of the parent chain above this contract, defaults to
+    /// `DEFAULT_MAX_PARENT_DEPTH`
+    #[serde(default)]
+    pub max_parent_depth: Option<u32>,
+    /// Limits the withdrawals to the vested part of the donations. It can't be changed later on.
+    #[serde(default)]
+    pub vesting: Option<VestingSchedule>,
+    /// Protocol fee taken from every donation. It can't be changed later on.
+    #[serde(default)]
+    pub fee: Option<FeeConfig>,
+}
+
+#[cw_serde]
+pub struct FeeConfig {
+    pub recipient: String,
+    /// Part of every donation going to the recipient
+    pub rate: Decimal,
+    pub mode: FeeMode,
+}
+
+#[cw_serde]
+pub enum FeeMode {
+    /// Fees are sent to the recipient with every donation
+    Immediate {},
+    /// Fees are kept by the contract until the recipient claims them
+    Accrued {},
+}
+
+#[cw_serde]
+pub enum VestingSchedule {
+    /// Donations unlock linearly between `start` and `end`
+    Linear { start: Timestamp, end: Timestamp },
+    /// Donations unlock all at once at `at`
+    Cliff { at: Timestamp },
 }
 
 #[cw_serde]
@@ -24,17 +93,656 @@ pub enum QueryMsg {
     Value {},
     #[returns(ValueResp)]
     Incremented { value: u64 },
-
+    #[returns(ParentResp)]
+    Parent {},
+    #[returns(ChildrenResp)]
+    Children {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AncestorsResp)]
+    Ancestors {},
+    #[returns(ReceiptsResp)]
+    Receipts {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(ReceiptResp)]
+    Receipt { id: u64 },
+    #[returns(MilestonesResp)]
+    UpcomingMilestones {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(MilestonesResp)]
+    AchievedMilestones {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(MatchingPoolResp)]
+    MatchingPool {},
+    #[returns(MatchedResp)]
+    Matched {
+        pool_id: u64,
+        donor: String,
+    },
+    /// Returns the given campaign, or the latest one if no id is passed
+    #[returns(CampaignResp)]
+    Campaign { id: Option<u64> },
+    #[returns(ContributionResp)]
+    CampaignContribution {
+        campaign_id: u64,
+        donor: String,
+    },
+    #[returns(VestingResp)]
+    Vesting {},
+    #[returns(ConfigResp)]
+    Config {},
+    #[returns(DonorsResp)]
+    Allowlist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(DonorsResp)]
+    Denylist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Funds credited to the donor by redeemed vouchers
+    #[returns(CreditsResp)]
+    Credits { donor: String },
+    #[returns(CounterResp)]
+    Counter { id: String },
+    #[returns(CountersResp)]
+    ListCounters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Value of the counter at the end of the block at `height`
+    #[returns(ValueAtResp)]
+    ValueAt {
+        height: u64,
+        counter_id: Option<String>,
+    },
+    /// Changes of the counter value, oldest first
+    #[returns(ValueHistoryResp)]
+    ValueHistory {
+        counter_id: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(ProposalResp)]
+    Proposal { id: u64 },
+    #[returns(ProposalsResp)]
+    Proposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(VotesResp)]
+    Votes {
+        proposal_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(VotingWeightResp)]
+    VotingWeight { donor: String },
+    /// Minimal donation of the default counter and its scheduled change
+    #[returns(MinimalDonationResp)]
+    MinimalDonation {},
+    #[returns(IbcChannelsResp)]
+    IbcChannels {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(IbcAllowlistResp)]
+    IbcAllowlist {
+        start_after: Option<IbcCounterparty>,
+        limit: Option<u32>,
+    },
+    /// Share accrued for `parent` in the pull payout mode
+    #[returns(ParentShareResp)]
+    ParentShare { parent: String },
+    #[returns(SubscriptionResp)]
+    Subscription { id: u64 },
+    #[returns(SubscriptionsResp)]
+    Subscriptions {
+        donor: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Subscriptions ordered by the time their next instalment is due, then by id
+    #[returns(SubscriptionsResp)]
+    SubscriptionsByDue {
+        start_after: Option<(Timestamp, u64)>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
 pub enum ExecMsg {
-    Donate {},
-    Withdraw {},
+    Donate {
+        /// Number of contracts the donation was already forwarded through, set by children. Ignored
+        /// unless the sender is a child approved by the owner.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        depth: Option<u32>,
+        /// Counter to donate to, the default one if not given
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        counter_id: Option<String>,
+    },
+    /// Owner only, withdraws `amount` or everything which can be withdrawn if not given
+    Withdraw {
+        #[serde(default)]
+        amount: Option<Vec<Coin>>,
+    },
+    /// Counters created with `CreateCounter` can only be reset by their owner
     Reset {
         #[serde(default)]
         counter: u64,
-    }
+        #[serde(default)]
+        counter_id: Option<String>,
+    },
+    /// Sent by a child contract to its parent when it is configured to donate to it
+    RegisterChild {},
+    /// Owner only, donations forwarded by approved children skip the allowlist and denylist
+    SetChildApproval {
+        child: String,
+        approved: bool,
+    },
+    /// Owner only, `None` disables receipts for further donations
+    SetReceiptMode {
+        mode: Option<ReceiptMode>,
+    },
+    /// Owner only, triggers `action` when a donation brings the counter to `counter`
+    AddMilestone {
+        counter: u64,
+        action: MilestoneAction,
+    },
+    /// Owner only
+    RemoveMilestone {
+        counter: u64,
+    },
+    /// Escrows the single sent coin as a matching pool. Until `expires_at`, every counted donation
+    /// in the pool denom is matched with `ratio` of its amount, up to `cap` per donor.
+    FundMatchingPool {
+        ratio: Decimal,
+        cap: Option<Uint128>,
+        expires_at: Timestamp,
+    },
+    /// Sponsor only, returns the unmatched funds after the pool expires
+    ReclaimMatchingPool {},
+    /// Owner only. Counted donations between `start` (now by default) and `end` are escrowed for
+    /// the campaign until it is finalized.
+    StartCampaign {
+        start: Option<Timestamp>,
+        end: Timestamp,
+        goal: Vec<Coin>,
+        release: CampaignRelease,
+    },
+    /// Callable by anyone after the campaign ends. If the goal is reached for every denom, raised
+    /// funds are released, otherwise donors can claim their refunds.
+    FinalizeCampaign {},
+    ClaimRefund {
+        campaign_id: u64,
+    },
+    /// Fee recipient only, sends the accrued fees
+    ClaimFees {},
+    /// Owner only
+    SetDonorMode {
+        mode: DonorMode,
+    },
+    /// Owner only, addresses are added before the removed ones are removed
+    UpdateAllowlist {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+    /// Owner only, addresses are added before the removed ones are removed
+    UpdateDenylist {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+    /// Owner only, `None` disables vouchers
+    SetVoucherSigner {
+        signer: Option<VoucherSigner>,
+    },
+    /// Owner only, adds a counter with its own minimal donation and parent. Donations to the parent
+    /// go to its default counter, and only the parent of the default counter tracks this contract
+    /// as its child.
+    CreateCounter {
+        id: String,
+        #[serde(default)]
+        counter: u64,
+        minimal_donation: Coin,
+        /// Sender by default
+        owner: Option<String>,
+        parent: Option<Parent>,
+    },
+    /// Owner only. Counter history older than `blocks` is pruned with every counter change, `None`
+    /// keeps the whole history.
+    SetHistoryRetention {
+        blocks: Option<u64>,
+    },
+    /// Callable by anyone, prunes up to `limit` history entries outside of the retention window
+    PruneHistory {
+        counter_id: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Owner only, `None` disables creating new proposals
+    SetGovernance {
+        config: Option<GovernanceConfig>,
+    },
+    /// Donors only
+    Propose {
+        action: ProposalAction,
+    },
+    /// Donors only, the vote is weighted by everything the voter donated so far
+    Vote {
+        proposal_id: u64,
+        option: VoteOption,
+    },
+    /// Callable by anyone after the voting period of a passed proposal
+    ExecuteProposal {
+        proposal_id: u64,
+    },
+    /// Sent by the contract to itself when executing a proposal
+    ApplyProposal {
+        action: ProposalAction,
+    },
+    /// Counts a donation authorized off-chain. Callable by anyone, no funds are accepted.
+    RedeemVoucher {
+        voucher: Voucher,
+        signature: Binary,
+    },
+    /// Owner only, forwards donations to a parent on another chain. Used by the default counter
+    /// when it has no local parent.
+    SetIbcParent {
+        parent: Option<IbcParent>,
+    },
+    /// Owner only, counterparties are added before the removed ones are removed. Channels can only
+    /// be opened with allowed counterparties.
+    UpdateIbcAllowlist {
+        #[serde(default)]
+        add: Vec<IbcCounterparty>,
+        #[serde(default)]
+        remove: Vec<IbcCounterparty>,
+    },
+    /// Owner only, changes the minimal donation of the default counter right away, dropping a
+    /// scheduled change
+    UpdateMinimalDonation {
+        minimal_donation: Coin,
+    },
+    /// Owner only, changes the minimal donation of the default counter at a future block height,
+    /// replacing a change scheduled before. The change is applied by the first donation at or
+    /// after the height.
+    ScheduleMinimalDonation {
+        minimal_donation: Coin,
+        height: u64,
+    },
+    /// Owner only, replaces the fixed minimal donation of the default counter with one normalized
+    /// by a price oracle. `None` goes back to the fixed minimal donation.
+    SetOracle {
+        config: Option<OracleConfig>,
+    },
+    /// Owner only, swaps donated or withdrawn coins into the treasury denom. `None` disables swapping.
+    SetSwap {
+        config: Option<SwapConfig>,
+    },
+    /// Callable by anyone, donates the share accrued for `parent` (the sender if not given) to it
+    ClaimParentShare {
+        #[serde(default)]
+        parent: Option<String>,
+    },
+    /// Escrows the sent funds to donate `amount` to the counter every `interval` seconds, starting
+    /// right away. The subscription ends once the funds left can't cover another instalment.
+    Subscribe {
+        #[serde(default)]
+        counter_id: Option<String>,
+        amount: Vec<Coin>,
+        interval: u64,
+    },
+    /// Callable by anyone, donates the due instalments of up to `limit` subscriptions, earliest
+    /// due first. Subscriptions whose instalment fails are ended and refunded.
+    ProcessSubscriptions {
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    /// Sent by the contract to itself for every due instalment, so a failing one doesn't revert the
+    /// others
+    PayInstalment {
+        id: u64,
+    },
+    /// Donor only, ends the subscription and returns the funds left
+    CancelSubscription {
+        id: u64,
+    },
+    /// Owner only, executes up to 10 admin messages in order, at most one of them a withdrawal.
+    /// Fails as a whole if any of them fails.
+    Batch {
+        msgs: Vec<AdminMsg>,
+    },
+}
+
+/// Owner only operations which can be batched, see the `ExecMsg` variants of the same name
+#[cw_serde]
+pub enum AdminMsg {
+    /// Replaces the parent of the default counter
+    UpdateParent {
+        parent: Option<Parent>,
+    },
+    /// Changes the minimal donation of the default counter
+    UpdateMinimalDonation {
+        minimal_donation: Coin,
+    },
+    ScheduleMinimalDonation {
+        minimal_donation: Coin,
+        height: u64,
+    },
+    Withdraw {
+        amount: Option<Vec<Coin>>,
+    },
+    SetReceiptMode {
+        mode: Option<ReceiptMode>,
+    },
+    AddMilestone {
+        counter: u64,
+        action: MilestoneAction,
+    },
+    RemoveMilestone {
+        counter: u64,
+    },
+    SetDonorMode {
+        mode: DonorMode,
+    },
+    SetChildApproval {
+        child: String,
+        approved: bool,
+    },
+    UpdateAllowlist {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+    UpdateDenylist {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+    SetVoucherSigner {
+        signer: Option<VoucherSigner>,
+    },
+    SetHistoryRetention {
+        blocks: Option<u64>,
+    },
+    SetGovernance {
+        config: Option<GovernanceConfig>,
+    },
+    SetIbcParent {
+        parent: Option<IbcParent>,
+    },
+    UpdateIbcAllowlist {
+        #[serde(default)]
+        add: Vec<IbcCounterparty>,
+        #[serde(default)]
+        remove: Vec<IbcCounterparty>,
+    },
+    SetOracle {
+        config: Option<OracleConfig>,
+    },
+    SetSwap {
+        config: Option<SwapConfig>,
+    },
+}
+
+/// Coins in other denoms than `treasury_denom` are swapped through `contract`. The output may fall
+/// short of the simulated one by at most `max_slippage`. Coins which can't be swapped are kept.
+#[cw_serde]
+pub struct SwapConfig {
+    pub contract: String,
+    pub treasury_denom: String,
+    pub max_slippage: Decimal,
+    pub trigger: SwapTrigger,
+}
+
+#[cw_serde]
+pub enum SwapTrigger {
+    /// Donations left in the contract are swapped right away
+    Donate {},
+    /// Withdrawn coins are swapped on their way to the owner
+    Withdraw {},
+}
+
+/// Subset of the swap contract interface used to swap into the treasury denom
+#[cw_serde]
+pub enum SwapExecMsg {
+    /// Swaps the sent coin, sending the output to `recipient` or the sender
+    Swap {
+        ask_denom: String,
+        min_output: Uint128,
+        recipient: Option<String>,
+    },
+}
+
+#[cw_serde]
+pub enum SwapQueryMsg {
+    Simulate { offer: Coin, ask_denom: String },
+}
+
+#[cw_serde]
+pub struct SimulationResp {
+    pub amount: Uint128,
+}
+
+/// A donation is counted if any coin in one of `denoms` is worth at least `minimum` reference
+/// units according to the oracle. Prices older than `max_age` seconds are rejected.
+#[cw_serde]
+pub struct OracleConfig {
+    pub oracle: String,
+    pub minimum: Uint128,
+    pub denoms: Vec<String>,
+    pub max_age: u64,
+}
+
+/// Subset of the price feed query interface used to normalize donations
+#[cw_serde]
+pub enum OracleQueryMsg {
+    /// Price of one unit of `denom` in reference units
+    Price { denom: String },
+}
+
+#[cw_serde]
+pub struct PriceResp {
+    pub price: Decimal,
+    pub updated_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct ScheduledMinimalDonation {
+    pub minimal_donation: Coin,
+    pub height: u64,
+}
+
+/// Overrides available to chain governance
+#[cw_serde]
+pub enum SudoMsg {
+    SetOwner {
+        owner: String,
+    },
+    /// Resets any counter, regardless of its owner
+    ForceReset {
+        counter: u64,
+        #[serde(default)]
+        counter_id: Option<String>,
+    },
+    /// Sends the whole donation balance to `to`, ignoring the vesting schedule
+    ForceWithdraw {
+        to: String,
+    },
+    /// Replaces the parent of the default counter
+    UpdateParent {
+        parent: Option<Parent>,
+    },
+}
+
+/// Parent counting contract on another chain. Every counted donation to the default counter
+/// forwards `part` of the donated coins: a donation notice is sent over `channel_id` first, and
+/// the funds are transferred over `transfer_channel_id` once the parent acknowledges it. Funds of
+/// rejected or timed out notices stay with this contract.
+#[cw_serde]
+pub struct IbcParent {
+    /// Channel to the parent contract, opened with the `counting-1` version
+    pub channel_id: String,
+    /// ICS-20 channel to the parent's chain
+    pub transfer_channel_id: String,
+    /// Address of the parent contract on its chain
+    pub addr: String,
+    pub part: Decimal,
+    /// Timeout of notices and transfers in seconds
+    pub timeout: u64,
+}
+
+/// Counting contract on another chain, identified by the connection to its chain and its port
+#[cw_serde]
+pub struct IbcCounterparty {
+    pub connection_id: String,
+    pub port_id: String,
+}
+
+/// Packets exchanged between counting contracts
+#[cw_serde]
+pub enum IbcPacketMsg {
+    /// Notice of a donation forwarded by a child, `amount` is in the denoms of the child's chain. It
+    /// is counted if it reaches the minimal donation of the parent.
+    Donation {
+        forward_id: u64,
+        amount: Vec<Coin>,
+    },
+}
+
+#[cw_serde]
+pub enum IbcAck {
+    Ok {},
+    Error { error: String },
+}
+
+/// Voting weight is the amount a donor directly donated in the denom of the default counter's
+/// minimal donation.
+#[cw_serde]
+pub struct GovernanceConfig {
+    /// Voting period in seconds
+    pub voting_period: u64,
+    /// Part of the total weight which has to vote for the proposal to be valid
+    pub quorum: Decimal,
+    /// Part of the cast weight which has to be exceeded by yes votes for the proposal to pass
+    pub threshold: Decimal,
+}
+
+/// Parameter changes donors can vote on. They apply to the default counter.
+#[cw_serde]
+pub enum ProposalAction {
+    UpdateMinimalDonation { minimal_donation: Coin },
+    UpdateParent { parent: Option<Parent> },
+    UpdateOwner { owner: String },
+}
+
+#[cw_serde]
+pub enum VoteOption {
+    Yes,
+    No,
+}
+
+#[cw_serde]
+pub enum ProposalStatus {
+    Open,
+    Passed,
+    Rejected,
+    Executed,
+}
+
+/// Public key vouchers are signed with. Secp256k1 signatures are made over the SHA-256 digest of
+/// the JSON encoded voucher, ed25519 ones over the JSON encoded voucher itself.
+#[cw_serde]
+pub enum VoucherSigner {
+    Secp256k1 { pubkey: Binary },
+    Ed25519 { pubkey: Binary },
+}
+
+#[cw_serde]
+pub struct Voucher {
+    /// Contract the voucher is issued for
+    pub contract: String,
+    pub donor: String,
+    /// Can be used only once per donor
+    pub nonce: u64,
+    pub expires_at: Timestamp,
+    /// Recorded in the donor credits, the funds are not transferred
+    #[serde(default)]
+    pub credit: Vec<Coin>,
+}
+
+/// Which donors are accepted. Denylisted donors are rejected in every mode. Children forwarding
+/// their share are always accepted.
+#[cw_serde]
+pub enum DonorMode {
+    Open {},
+    /// Only allowlisted donors are accepted
+    Allowlist {},
+}
+
+/// Where the funds of a successful campaign go
+#[cw_serde]
+pub enum CampaignRelease {
+    Owner {},
+    Parent {},
+}
+
+#[cw_serde]
+pub enum CampaignStatus {
+    Active,
+    Succeeded,
+    Failed,
+}
+
+#[cw_serde]
+pub enum MilestoneAction {
+    /// Pays the bonus out of the contract balance to the donor reaching the milestone. If the
+    /// balance is not sufficient, whatever is available is paid.
+    Bonus { amount: Vec<Coin> },
+    /// Only emits the `milestone` event
+    Event {},
+    /// Executes `msg` on `contract`. A failing execution fails the donation reaching the milestone.
+    Notify { contract: String, msg: Binary },
+}
+
+#[cw_serde]
+pub enum ReceiptMode {
+    /// Receipts are only recorded in this contract
+    Internal {},
+    /// Receipts are additionally minted on a CW721 contract, which is expected to be non-transferable
+    External { nft_contract: String },
+}
+
+/// Subset of the CW721 execute interface used to mint receipts on an external contract
+#[cw_serde]
+pub enum Cw721ExecMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: ReceiptExtension,
+    },
+}
+
+#[cw_serde]
+pub struct ReceiptExtension {
+    pub counter: u64,
+    pub amount: Vec<Coin>,
+    pub time: Timestamp,
 }
 
 #[cw_serde]
@@ -42,7 +750,254 @@ pub struct ValueResp {
     pub value: u64,
 }
 
+#[cw_serde]
+pub struct ParentResp {
+    pub parent: Option<Parent>,
+}
+
+#[cw_serde]
+pub struct ChildResp {
+    pub addr: String,
+    /// Total funds forwarded by the child so far
+    pub received: Vec<Coin>,
+    pub approved: bool,
+}
+
+#[cw_serde]
+pub struct ChildrenResp {
+    pub children: Vec<ChildResp>,
+}
+
+#[cw_serde]
+pub struct AncestorsResp {
+    /// Parent chain starting with the direct parent
+    pub ancestors: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ReceiptResp {
+    pub id: u64,
+    pub owner: String,
+    /// Counter value after the donation was counted
+    pub counter: u64,
+    pub amount: Vec<Coin>,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct ParentShareResp {
+    pub accrued: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct SubscriptionResp {
+    pub id: u64,
+    pub donor: String,
+    pub counter_id: String,
+    pub amount: Vec<Coin>,
+    pub interval: u64,
+    pub next_due: Timestamp,
+    /// Escrowed funds left for further instalments
+    pub remaining: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct SubscriptionsResp {
+    pub subscriptions: Vec<SubscriptionResp>,
+}
+
+#[cw_serde]
+pub struct ReceiptsResp {
+    pub receipts: Vec<ReceiptResp>,
+}
+
+#[cw_serde]
+pub struct MilestoneResp {
+    pub counter: u64,
+    pub action: MilestoneAction,
+    /// Donor whose donation reached the milestone, or the owner if a reset skipped it
+    pub achieved_by: Option<String>,
+    /// Block height the milestone was reached at
+    pub achieved_at: Option<u64>,
+}
+
+#[cw_serde]
+pub struct MilestonesResp {
+    pub milestones: Vec<MilestoneResp>,
+}
+
+#[cw_serde]
+pub struct MatchingPoolInfo {
+    pub id: u64,
+    pub sponsor: String,
+    /// Funds still available for matching
+    pub remaining: Coin,
+    pub ratio: Decimal,
+    pub cap: Option<Uint128>,
+    pub expires_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct MatchingPoolResp {
+    pub pool: Option<MatchingPoolInfo>,
+}
+
+#[cw_serde]
+pub struct MatchedResp {
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct CampaignResp {
+    pub id: u64,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub goal: Vec<Coin>,
+    pub raised: Vec<Coin>,
+    pub release: CampaignRelease,
+    pub status: CampaignStatus,
+}
+
+#[cw_serde]
+pub struct ContributionResp {
+    /// Funds donated to the campaign and not refunded yet
+    pub amount: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct VestingResp {
+    pub schedule: Option<VestingSchedule>,
+    /// Part of all donations made so far which is already vested
+    pub unlocked: Vec<Coin>,
+    pub withdrawn: Vec<Coin>,
+    pub withdrawable: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct ConfigResp {
+    pub owner: String,
+    pub minimal_donation: Coin,
+    pub max_parent_depth: u32,
+    pub fee: Option<FeeConfig>,
+    /// Total fees taken from donations so far
+    pub fees_collected: Vec<Coin>,
+    /// Fees waiting to be claimed by the recipient
+    pub fees_accrued: Vec<Coin>,
+    pub donor_mode: DonorMode,
+    pub voucher_signer: Option<VoucherSigner>,
+    pub history_retention: Option<u64>,
+    pub ibc_parent: Option<IbcParent>,
+    pub oracle: Option<OracleConfig>,
+    pub swap: Option<SwapConfig>,
+}
+
+#[cw_serde]
+pub struct ValueAtResp {
+    /// `None` if the counter didn't exist at the height or its history was pruned
+    pub value: Option<u64>,
+}
+
+#[cw_serde]
+pub struct HistoryEntry {
+    pub height: u64,
+    pub value: u64,
+}
+
+#[cw_serde]
+pub struct ValueHistoryResp {
+    pub history: Vec<HistoryEntry>,
+}
+
+#[cw_serde]
+pub struct CounterResp {
+    pub id: String,
+    pub value: u64,
+    pub minimal_donation: Coin,
+    pub owner: Option<String>,
+    pub parent: Option<Parent>,
+}
+
+#[cw_serde]
+pub struct CountersResp {
+    pub counters: Vec<CounterResp>,
+}
+
+#[cw_serde]
+pub struct ProposalResp {
+    pub id: u64,
+    pub proposer: String,
+    pub action: ProposalAction,
+    pub end: Timestamp,
+    /// Total voting weight when the proposal was created
+    pub total_weight: Uint128,
+    pub yes: Uint128,
+    pub no: Uint128,
+    pub status: ProposalStatus,
+}
+
+#[cw_serde]
+pub struct ProposalsResp {
+    pub proposals: Vec<ProposalResp>,
+}
+
+#[cw_serde]
+pub struct VoteResp {
+    pub voter: String,
+    pub option: VoteOption,
+    pub weight: Uint128,
+}
+
+#[cw_serde]
+pub struct VotesResp {
+    pub votes: Vec<VoteResp>,
+}
+
+#[cw_serde]
+pub struct VotingWeightResp {
+    pub weight: Uint128,
+    pub total: Uint128,
+}
+
+#[cw_serde]
+pub struct CreditsResp {
+    pub amount: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct DonorsResp {
+    pub donors: Vec<String>,
+}
+
 #[cw_serde]
 pub struct MigrateMsg {
     pub parent: Option<Parent>,
+    /// Switches the payout of every configured parent
+    #[serde(default)]
+    pub parent_payout: Option<ParentPayout>,
+}
+
+#[cw_serde]
+pub struct IbcAllowlistResp {
+    pub counterparties: Vec<IbcCounterparty>,
+}
+
+#[cw_serde]
+pub struct IbcChannelResp {
+    pub channel_id: String,
+    pub counterparty_port_id: String,
+    pub counterparty_channel_id: String,
+    /// Donations notified by children over the channel
+    pub received: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct IbcChannelsResp {
+    pub channels: Vec<IbcChannelResp>,
+}
+
+#[cw_serde]
+pub struct MinimalDonationResp {
+    pub current: Coin,
+    /// Change which is not effective yet
+    pub pending: Option<ScheduledMinimalDonation>,
 }
@@ -1,10 +1,50 @@
 #[cfg(test)]
 mod tests;
-use cosmwasm_std::{Addr, Coin, Empty, StdError, StdResult};
-use cw_multi_test::{App, ContractWrapper, Executor};
+#[cfg(test)]
+mod proptests;
+pub mod fixtures;
+pub mod nft;
+pub mod oracle;
+pub mod swap;
+
+use cosmwasm_std::{Addr, Coin, Decimal, StdError, StdResult, Timestamp, Uint128};
+use cw_multi_test::{App, AppResponse, ContractWrapper, Executor};
+use thiserror::Error;
 use crate::error::ContractError;
-use crate::msg::{ValueResp, InstantiateMsg, QueryMsg, ExecMsg, Parent, MigrateMsg};
-use crate::{execute, query, instantiate, migrate};
+use crate::msg::{ValueResp, InstantiateMsg, QueryMsg, ExecMsg, Parent, MigrateMsg, ParentResp, ChildrenResp, AncestorsResp,
+                 ReceiptMode, ReceiptResp, ReceiptsResp, MilestoneAction, MilestonesResp, MatchingPoolResp, MatchedResp,
+                 CampaignRelease, CampaignResp, ContributionResp, VestingSchedule, VestingResp, FeeConfig, ConfigResp,
+                 DonorMode, DonorsResp, Voucher, VoucherSigner, CreditsResp, CounterResp, CountersResp,
+                 ValueAtResp, ValueHistoryResp, GovernanceConfig, ProposalAction, VoteOption, ProposalResp, VotesResp,
+                 VotingWeightResp, SudoMsg, AdminMsg, MinimalDonationResp, OracleConfig, SwapConfig,
+                 SubscriptionsResp, ParentShareResp};
+use crate::{execute, query, instantiate, migrate, reply, sudo};
+
+/// Error returned by the multitest helpers. Errors coming out of the `App` are converted
+/// instead of unwrapped, so unexpected error types fail the assertion rather than the helper.
+#[derive(Error, Debug, PartialEq)]
+pub enum TestError {
+    #[error("{0}")]
+    Contract(#[from] ContractError),
+
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unexpected error: {0}")]
+    Other(String),
+}
+
+impl From<anyhow::Error> for TestError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<ContractError>() {
+            Ok(err) => TestError::Contract(err),
+            Err(err) => match err.downcast::<StdError>() {
+                Ok(err) => TestError::Std(err),
+                Err(err) => TestError::Other(err.to_string()),
+            },
+        }
+    }
+}
 
 pub struct CountingContract(Addr);
 
@@ -13,65 +53,553 @@ impl CountingContract {
         &self.0
     }
 
-    pub fn store_code(app: &mut cw_multi_test::App) -> u64 {
-        let contract = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
+    pub fn store_code(app: &mut App) -> u64 {
+        let contract = ContractWrapper::new(execute, instantiate, query)
+            .with_sudo(sudo)
+            .with_reply(reply)
+            .with_migrate(migrate);
         app.store_code(Box::new(contract))
     }
 
+    pub fn builder<'a>(app: &'a mut App, code_id: u64, sender: &Addr, minimal_donation: Coin) -> InstantiateBuilder<'a> {
+        InstantiateBuilder {
+            app,
+            code_id,
+            sender: sender.clone(),
+            label: "Counting contract".to_owned(),
+            admin: None,
+            funds: vec![],
+            msg: InstantiateMsg { counter: 1, minimal_donation, parent: None, max_parent_depth: None, vesting: None, fee: None },
+        }
+    }
+
     #[track_caller]
     pub fn instantiate(app: &mut App, code_id: u64, sender: &Addr, admin: Option<&Addr>, label: &str,
-                       minimal_donation: Coin, parent: Option<Parent>) -> StdResult<CountingContract> {
-        app.instantiate_contract(
-            code_id,
-            sender.clone(),
-            &InstantiateMsg { counter: 1, minimal_donation, parent },
-            &[],
-            label,
-            admin.map(Addr::to_string)
-        ).map_err(|err| err.downcast().unwrap()).map(CountingContract)
+                       minimal_donation: Coin, parent: Option<Parent>) -> Result<CountingContract, TestError> {
+        let mut builder = Self::builder(app, code_id, sender, minimal_donation).with_label(label);
+
+        if let Some(admin) = admin {
+            builder = builder.with_admin(admin);
+        }
+
+        if let Some(parent) = parent {
+            builder = builder.with_parent(parent);
+        }
+
+        builder.call()
     }
 
     #[track_caller]
-    pub fn migrate(app: &mut App, sender: &Addr, contract: &Addr, code_id: u64, parent: Option<Parent>) -> StdResult<Self> {
+    pub fn migrate(app: &mut App, sender: &Addr, contract: &Addr, code_id: u64, parent: Option<Parent>) -> Result<Self, TestError> {
+        Self::migrate_with(app, sender, contract, code_id, &MigrateMsg { parent, parent_payout: None })
+    }
+
+    #[track_caller]
+    pub fn migrate_with(app: &mut App, sender: &Addr, contract: &Addr, code_id: u64, msg: &MigrateMsg)
+        -> Result<Self, TestError> {
         app.migrate_contract(
             sender.clone(),
             contract.clone(),
-            &MigrateMsg { parent },
+            msg,
             code_id
-        ).map_err(|err| err.downcast::<StdError>().unwrap())?;
+        )?;
 
         Ok(CountingContract(contract.clone()))
+    }
+
+    #[track_caller]
+    pub fn donate(&self, app: &mut App, sender: &Addr, funds: &[Coin]) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Donate { depth: None, counter_id: None }, funds)
+    }
+
+    #[track_caller]
+    pub fn donate_to(&self, app: &mut App, sender: &Addr, counter_id: &str, funds: &[Coin]) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Donate { depth: None, counter_id: Some(counter_id.to_owned()) }, funds)
+    }
+
+    #[track_caller]
+    pub fn reset_counter(&self, app: &mut App, sender: &Addr, counter_id: &str, counter: u64) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Reset { counter, counter_id: Some(counter_id.to_owned()) }, &[])
+    }
+
+    #[track_caller]
+    pub fn create_counter(&self, app: &mut App, sender: &Addr, id: &str, minimal_donation: Coin, owner: Option<&Addr>,
+                          parent: Option<Parent>) -> Result<AppResponse, TestError> {
+        let msg = ExecMsg::CreateCounter {
+            id: id.to_owned(),
+            counter: 0,
+            minimal_donation,
+            owner: owner.map(Addr::to_string),
+            parent,
+        };
+        self.execute(app, sender, &msg, &[])
+    }
+
+    #[track_caller]
+    pub fn set_history_retention(&self, app: &mut App, sender: &Addr, blocks: Option<u64>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetHistoryRetention { blocks }, &[])
+    }
+
+    #[track_caller]
+    pub fn prune_history(&self, app: &mut App, sender: &Addr, limit: Option<u32>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::PruneHistory { counter_id: None, limit }, &[])
+    }
 
+    #[track_caller]
+    pub fn set_governance(&self, app: &mut App, sender: &Addr, config: Option<GovernanceConfig>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetGovernance { config }, &[])
+    }
+
+    #[track_caller]
+    pub fn propose(&self, app: &mut App, sender: &Addr, action: ProposalAction) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Propose { action }, &[])
+    }
+
+    #[track_caller]
+    pub fn vote(&self, app: &mut App, sender: &Addr, proposal_id: u64, option: VoteOption) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Vote { proposal_id, option }, &[])
+    }
+
+    #[track_caller]
+    pub fn execute_proposal(&self, app: &mut App, sender: &Addr, proposal_id: u64) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::ExecuteProposal { proposal_id }, &[])
+    }
+
+    #[track_caller]
+    pub fn update_minimal_donation(&self, app: &mut App, sender: &Addr, minimal_donation: Coin) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::UpdateMinimalDonation { minimal_donation }, &[])
+    }
+
+    #[track_caller]
+    pub fn schedule_minimal_donation(&self, app: &mut App, sender: &Addr, minimal_donation: Coin, height: u64)
+        -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::ScheduleMinimalDonation { minimal_donation, height }, &[])
+    }
+
+    #[track_caller]
+    pub fn set_oracle(&self, app: &mut App, sender: &Addr, config: Option<OracleConfig>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetOracle { config }, &[])
+    }
+
+    #[track_caller]
+    pub fn set_swap(&self, app: &mut App, sender: &Addr, config: Option<SwapConfig>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetSwap { config }, &[])
+    }
+
+    #[track_caller]
+    pub fn claim_parent_share(&self, app: &mut App, sender: &Addr, parent: Option<&Addr>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::ClaimParentShare { parent: parent.map(Addr::to_string) }, &[])
+    }
+
+    #[track_caller]
+    pub fn subscribe(&self, app: &mut App, sender: &Addr, amount: &[Coin], interval: u64, funds: &[Coin])
+        -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Subscribe { counter_id: None, amount: amount.to_vec(), interval }, funds)
+    }
+
+    #[track_caller]
+    pub fn process_subscriptions(&self, app: &mut App, sender: &Addr, limit: Option<u32>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::ProcessSubscriptions { limit }, &[])
+    }
+
+    #[track_caller]
+    pub fn cancel_subscription(&self, app: &mut App, sender: &Addr, id: u64) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::CancelSubscription { id }, &[])
+    }
+
+    #[track_caller]
+    pub fn batch(&self, app: &mut App, sender: &Addr, msgs: Vec<AdminMsg>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Batch { msgs }, &[])
     }
 
     #[track_caller]
-    pub fn donate(&self, app: &mut App, sender: &Addr, funds: &[Coin]) -> Result<(), ContractError> {
+    pub fn sudo(&self, app: &mut App, msg: &SudoMsg) -> Result<AppResponse, TestError> {
+        app.wasm_sudo(self.0.clone(), msg).map_err(TestError::from)
+    }
+
+    #[track_caller]
+    pub fn reset(&self, app: &mut App, sender: &Addr, counter: u64) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Reset { counter, counter_id: None }, &[])
+    }
+
+    #[track_caller]
+    pub fn withdraw(&self, app: &mut App, sender: &Addr) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Withdraw { amount: None }, &[])
+    }
+
+    #[track_caller]
+    pub fn withdraw_amount(&self, app: &mut App, sender: &Addr, amount: &[Coin]) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::Withdraw { amount: Some(amount.to_vec()) }, &[])
+    }
+
+    #[track_caller]
+    pub fn set_receipt_mode(&self, app: &mut App, sender: &Addr, mode: Option<ReceiptMode>) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetReceiptMode { mode }, &[])
+    }
+
+    #[track_caller]
+    pub fn set_donor_mode(&self, app: &mut App, sender: &Addr, mode: DonorMode) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetDonorMode { mode }, &[])
+    }
+
+    #[track_caller]
+    pub fn update_allowlist(&self, app: &mut App, sender: &Addr, add: &[&Addr], remove: &[&Addr])
+        -> Result<AppResponse, TestError> {
+        let add = add.iter().map(|addr| addr.to_string()).collect();
+        let remove = remove.iter().map(|addr| addr.to_string()).collect();
+        self.execute(app, sender, &ExecMsg::UpdateAllowlist { add, remove }, &[])
+    }
+
+    #[track_caller]
+    pub fn update_denylist(&self, app: &mut App, sender: &Addr, add: &[&Addr], remove: &[&Addr])
+        -> Result<AppResponse, TestError> {
+        let add = add.iter().map(|addr| addr.to_string()).collect();
+        let remove = remove.iter().map(|addr| addr.to_string()).collect();
+        self.execute(app, sender, &ExecMsg::UpdateDenylist { add, remove }, &[])
+    }
+
+    #[track_caller]
+    pub fn set_voucher_signer(&self, app: &mut App, sender: &Addr, signer: Option<VoucherSigner>)
+        -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetVoucherSigner { signer }, &[])
+    }
+
+    #[track_caller]
+    pub fn redeem_voucher(&self, app: &mut App, sender: &Addr, voucher: Voucher, signature: &[u8])
+        -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::RedeemVoucher { voucher, signature: signature.into() }, &[])
+    }
+
+    #[track_caller]
+    pub fn add_milestone(&self, app: &mut App, sender: &Addr, counter: u64, action: MilestoneAction) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::AddMilestone { counter, action }, &[])
+    }
+
+    #[track_caller]
+    pub fn remove_milestone(&self, app: &mut App, sender: &Addr, counter: u64) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::RemoveMilestone { counter }, &[])
+    }
+
+    #[track_caller]
+    pub fn fund_matching_pool(&self, app: &mut App, sender: &Addr, funds: &[Coin], ratio: Decimal, cap: Option<Uint128>,
+                              expires_at: Timestamp) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::FundMatchingPool { ratio, cap, expires_at }, funds)
+    }
+
+    #[track_caller]
+    pub fn reclaim_matching_pool(&self, app: &mut App, sender: &Addr) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::ReclaimMatchingPool {}, &[])
+    }
+
+    #[track_caller]
+    pub fn start_campaign(&self, app: &mut App, sender: &Addr, start: Option<Timestamp>, end: Timestamp, goal: Vec<Coin>,
+                          release: CampaignRelease) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::StartCampaign { start, end, goal, release }, &[])
+    }
+
+    #[track_caller]
+    pub fn finalize_campaign(&self, app: &mut App, sender: &Addr) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::FinalizeCampaign {}, &[])
+    }
+
+    #[track_caller]
+    pub fn claim_refund(&self, app: &mut App, sender: &Addr, campaign_id: u64) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::ClaimRefund { campaign_id }, &[])
+    }
+
+    #[track_caller]
+    pub fn claim_fees(&self, app: &mut App, sender: &Addr) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::ClaimFees {}, &[])
+    }
+
+    #[track_caller]
+    pub fn register_child(&self, app: &mut App, sender: &Addr) -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::RegisterChild {}, &[])
+    }
+
+    #[track_caller]
+    pub fn set_child_approval(&self, app: &mut App, sender: &Addr, child: &Addr, approved: bool)
+        -> Result<AppResponse, TestError> {
+        self.execute(app, sender, &ExecMsg::SetChildApproval { child: child.to_string(), approved }, &[])
+    }
+
+    /// Executes an arbitrary message, for the cases the dedicated helpers don't cover.
+    #[track_caller]
+    pub fn execute(&self, app: &mut App, sender: &Addr, msg: &ExecMsg, funds: &[Coin]) -> Result<AppResponse, TestError> {
         app.execute_contract(
             sender.clone(),
             self.0.clone(),
-             &ExecMsg::Donate {},
+            msg,
             funds
-        ).map_err(|err| err.downcast::<ContractError>().unwrap())?;
+        ).map_err(TestError::from)
+    }
+
+    #[track_caller]
+    pub fn query_value(&self, app: &App) -> StdResult<ValueResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Value {})
+    }
 
-        Ok(())
+    #[track_caller]
+    pub fn query_incremented(&self, app: &App, value: u64) -> StdResult<ValueResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Incremented { value })
     }
 
     #[track_caller]
-    pub fn withdraw(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
-        app.execute_contract(
-            sender.clone(),
+    pub fn query_parent(&self, app: &App) -> StdResult<ParentResp> {
+        app.wrap().query_wasm_smart(
             self.0.clone(),
-            &ExecMsg::Withdraw {},
-            &[]
-        ).map_err(|err| err.downcast::<ContractError>().unwrap())?;
+            &QueryMsg::Parent {})
+    }
 
-        Ok(())
+    #[track_caller]
+    pub fn query_children(&self, app: &App, start_after: Option<&Addr>, limit: Option<u32>) -> StdResult<ChildrenResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Children { start_after: start_after.map(Addr::to_string), limit })
     }
 
     #[track_caller]
-    pub fn query_value(&self, app: &App) -> StdResult<ValueResp> {
+    pub fn query_receipts(&self, app: &App, owner: &Addr, start_after: Option<u64>, limit: Option<u32>) -> StdResult<ReceiptsResp> {
         app.wrap().query_wasm_smart(
             self.0.clone(),
-            &QueryMsg::Value {})
+            &QueryMsg::Receipts { owner: owner.to_string(), start_after, limit })
+    }
+
+    #[track_caller]
+    pub fn query_receipt(&self, app: &App, id: u64) -> StdResult<ReceiptResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Receipt { id })
+    }
+
+    #[track_caller]
+    pub fn query_upcoming_milestones(&self, app: &App, start_after: Option<u64>, limit: Option<u32>) -> StdResult<MilestonesResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::UpcomingMilestones { start_after, limit })
+    }
+
+    #[track_caller]
+    pub fn query_achieved_milestones(&self, app: &App, start_after: Option<u64>, limit: Option<u32>) -> StdResult<MilestonesResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::AchievedMilestones { start_after, limit })
+    }
+
+    #[track_caller]
+    pub fn query_matching_pool(&self, app: &App) -> StdResult<MatchingPoolResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::MatchingPool {})
+    }
+
+    #[track_caller]
+    pub fn query_matched(&self, app: &App, pool_id: u64, donor: &Addr) -> StdResult<MatchedResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Matched { pool_id, donor: donor.to_string() })
+    }
+
+    #[track_caller]
+    pub fn query_campaign(&self, app: &App, id: Option<u64>) -> StdResult<CampaignResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Campaign { id })
+    }
+
+    #[track_caller]
+    pub fn query_campaign_contribution(&self, app: &App, campaign_id: u64, donor: &Addr) -> StdResult<ContributionResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::CampaignContribution { campaign_id, donor: donor.to_string() })
+    }
+
+    #[track_caller]
+    pub fn query_vesting(&self, app: &App) -> StdResult<VestingResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Vesting {})
+    }
+
+    #[track_caller]
+    pub fn query_config(&self, app: &App) -> StdResult<ConfigResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Config {})
+    }
+
+    #[track_caller]
+    pub fn query_allowlist(&self, app: &App, start_after: Option<&Addr>, limit: Option<u32>) -> StdResult<DonorsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Allowlist { start_after: start_after.map(Addr::to_string), limit })
+    }
+
+    #[track_caller]
+    pub fn query_denylist(&self, app: &App, start_after: Option<&Addr>, limit: Option<u32>) -> StdResult<DonorsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Denylist { start_after: start_after.map(Addr::to_string), limit })
+    }
+
+    #[track_caller]
+    pub fn query_credits(&self, app: &App, donor: &Addr) -> StdResult<CreditsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Credits { donor: donor.to_string() })
+    }
+
+    #[track_caller]
+    pub fn query_counter(&self, app: &App, id: &str) -> StdResult<CounterResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Counter { id: id.to_owned() })
+    }
+
+    #[track_caller]
+    pub fn query_counters(&self, app: &App, start_after: Option<&str>, limit: Option<u32>) -> StdResult<CountersResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::ListCounters { start_after: start_after.map(str::to_owned), limit })
+    }
+
+    #[track_caller]
+    pub fn query_value_at(&self, app: &App, height: u64) -> StdResult<ValueAtResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::ValueAt { height, counter_id: None })
+    }
+
+    #[track_caller]
+    pub fn query_value_history(&self, app: &App, start_after: Option<u64>, limit: Option<u32>) -> StdResult<ValueHistoryResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::ValueHistory { counter_id: None, start_after, limit })
+    }
+
+    #[track_caller]
+    pub fn query_proposal(&self, app: &App, id: u64) -> StdResult<ProposalResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Proposal { id })
+    }
+
+    #[track_caller]
+    pub fn query_votes(&self, app: &App, proposal_id: u64) -> StdResult<VotesResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Votes { proposal_id, start_after: None, limit: None })
+    }
+
+    #[track_caller]
+    pub fn query_voting_weight(&self, app: &App, donor: &Addr) -> StdResult<VotingWeightResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::VotingWeight { donor: donor.to_string() })
+    }
+
+    #[track_caller]
+    pub fn query_minimal_donation(&self, app: &App) -> StdResult<MinimalDonationResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::MinimalDonation {})
+    }
+
+    #[track_caller]
+    pub fn query_parent_share(&self, app: &App, parent: &Addr) -> StdResult<ParentShareResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::ParentShare { parent: parent.to_string() })
+    }
+
+    #[track_caller]
+    pub fn query_subscriptions(&self, app: &App, donor: &Addr) -> StdResult<SubscriptionsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Subscriptions { donor: donor.to_string(), start_after: None, limit: None })
+    }
+
+    #[track_caller]
+    pub fn query_subscriptions_by_due(&self, app: &App, start_after: Option<(Timestamp, u64)>)
+        -> StdResult<SubscriptionsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::SubscriptionsByDue { start_after, limit: None })
+    }
+
+    #[track_caller]
+    pub fn query_ancestors(&self, app: &App) -> StdResult<AncestorsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Ancestors {})
+    }
+}
+
+/// Instantiation options for [`CountingContract`], created with [`CountingContract::builder`].
+pub struct InstantiateBuilder<'a> {
+    app: &'a mut App,
+    code_id: u64,
+    sender: Addr,
+    label: String,
+    admin: Option<String>,
+    funds: Vec<Coin>,
+    msg: InstantiateMsg,
+}
+
+impl<'a> InstantiateBuilder<'a> {
+    pub fn with_counter(mut self, counter: u64) -> Self {
+        self.msg.counter = counter;
+        self
+    }
+
+    pub fn with_parent(mut self, parent: Parent) -> Self {
+        self.msg.parent = Some(parent);
+        self
+    }
+
+    pub fn with_max_parent_depth(mut self, max_parent_depth: u32) -> Self {
+        self.msg.max_parent_depth = Some(max_parent_depth);
+        self
+    }
+
+    pub fn with_vesting(mut self, vesting: VestingSchedule) -> Self {
+        self.msg.vesting = Some(vesting);
+        self
+    }
+
+    pub fn with_fee(mut self, fee: FeeConfig) -> Self {
+        self.msg.fee = Some(fee);
+        self
+    }
+
+    pub fn with_admin(mut self, admin: &Addr) -> Self {
+        self.admin = Some(admin.to_string());
+        self
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = label.to_owned();
+        self
+    }
+
+    pub fn with_funds(mut self, funds: &[Coin]) -> Self {
+        self.funds = funds.to_vec();
+        self
+    }
+
+    #[track_caller]
+    pub fn call(self) -> Result<CountingContract, TestError> {
+        self.app.instantiate_contract(
+            self.code_id,
+            self.sender,
+            &self.msg,
+            &self.funds,
+            self.label,
+            self.admin,
+        ).map_err(TestError::from).map(CountingContract)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,69 @@
+use cosmwasm_std::{Addr, Coin, Decimal};
+use cw_multi_test::App;
+use crate::msg::{ForwardMode, Parent, ParentPayout};
+use super::{CountingContract, TestError};
+
+/// How a child in a [`Tree`] forwards donations to the root.
+#[derive(Clone, Debug)]
+pub struct ChildConfig {
+    pub donating_period: u64,
+    pub part: Decimal,
+}
+
+/// A root contract together with the children donating to it.
+pub struct Tree {
+    pub root: CountingContract,
+    pub children: Vec<CountingContract>,
+}
+
+/// Instantiates a root contract and one child per entry in `children`, all owned by `owner`.
+#[track_caller]
+pub fn tree(app: &mut App, code_id: u64, owner: &Addr, minimal_donation: Coin,
+            children: &[ChildConfig]) -> Result<Tree, TestError> {
+    let root = CountingContract::builder(app, code_id, owner, minimal_donation.clone())
+        .with_label("Root counting contract")
+        .call()?;
+
+    let children = children.iter()
+        .map(|child| {
+            CountingContract::builder(app, code_id, owner, minimal_donation.clone())
+                .with_label("Child counting contract")
+                .with_parent(Parent {
+                    addr: root.addr().to_string(),
+                    donating_period: child.donating_period,
+                    part: child.part,
+                    mode: ForwardMode::Period,
+                    payout: ParentPayout::Push,
+                })
+                .call()
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Tree { root, children })
+}
+
+/// Instantiates `depth` contracts where every contract donates to the previous one.
+/// The first element is the top of the chain.
+#[track_caller]
+pub fn chain(app: &mut App, code_id: u64, owner: &Addr, minimal_donation: Coin, depth: usize,
+             link: ChildConfig) -> Result<Vec<CountingContract>, TestError> {
+    let mut contracts: Vec<CountingContract> = Vec::with_capacity(depth);
+
+    for _ in 0..depth {
+        let mut builder = CountingContract::builder(app, code_id, owner, minimal_donation.clone());
+
+        if let Some(parent) = contracts.last() {
+            builder = builder.with_parent(Parent {
+                addr: parent.addr().to_string(),
+                donating_period: link.donating_period,
+                part: link.part,
+                mode: ForwardMode::Period,
+                payout: ParentPayout::Push,
+            });
+        }
+
+        contracts.push(builder.call()?);
+    }
+
+    Ok(contracts)
+}
@@ -0,0 +1,106 @@
+//! Minimal CW721 stand-in accepting receipt mints, so the external receipt mode can be tested
+//! without pulling a full NFT implementation.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, to_binary};
+use cw_multi_test::{App, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+use crate::msg::{Cw721ExecMsg, ReceiptExtension};
+use super::TestError;
+
+const MINTER: Item<Addr> = Item::new("minter");
+const TOKENS: Map<&str, Token> = Map::new("tokens");
+
+#[cw_serde]
+pub struct Token {
+    pub owner: Addr,
+    pub extension: ReceiptExtension,
+}
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub minter: String,
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    NftInfo { token_id: String },
+    NumTokens {},
+}
+
+#[cw_serde]
+pub struct NumTokensResp {
+    pub count: u64,
+}
+
+fn instantiate(deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+    MINTER.save(deps.storage, &deps.api.addr_validate(&msg.minter)?)?;
+    Ok(Response::new())
+}
+
+fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: Cw721ExecMsg) -> StdResult<Response> {
+    match msg {
+        Cw721ExecMsg::Mint { token_id, owner, extension, .. } => {
+            if info.sender != MINTER.load(deps.storage)? {
+                return Err(StdError::generic_err("Only the minter can mint"));
+            }
+
+            if TOKENS.has(deps.storage, &token_id) {
+                return Err(StdError::generic_err("Token already minted"));
+            }
+
+            let owner = deps.api.addr_validate(&owner)?;
+            TOKENS.save(deps.storage, &token_id, &Token { owner, extension })?;
+        }
+    }
+
+    Ok(Response::new())
+}
+
+fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::NftInfo { token_id } => to_binary(&TOKENS.load(deps.storage, &token_id)?),
+        QueryMsg::NumTokens {} => to_binary(&NumTokensResp {
+            count: TOKENS.keys(deps.storage, None, None, Order::Ascending).count() as u64,
+        }),
+    }
+}
+
+pub struct NftContract(Addr);
+
+impl NftContract {
+    pub fn addr(&self) -> &Addr {
+        &self.0
+    }
+
+    pub fn store_code(app: &mut App) -> u64 {
+        let contract = ContractWrapper::new(execute, instantiate, query);
+        app.store_code(Box::new(contract))
+    }
+
+    #[track_caller]
+    pub fn instantiate(app: &mut App, code_id: u64, sender: &Addr, minter: &Addr) -> Result<Self, TestError> {
+        app.instantiate_contract(
+            code_id,
+            sender.clone(),
+            &InstantiateMsg { minter: minter.to_string() },
+            &[],
+            "Receipts NFT",
+            None,
+        ).map_err(TestError::from).map(NftContract)
+    }
+
+    #[track_caller]
+    pub fn query_token(&self, app: &App, token_id: &str) -> StdResult<Token> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::NftInfo { token_id: token_id.to_owned() })
+    }
+
+    #[track_caller]
+    pub fn query_num_tokens(&self, app: &App) -> StdResult<NumTokensResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::NumTokens {})
+    }
+}
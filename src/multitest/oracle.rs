@@ -0,0 +1,79 @@
+//! Price feed stand-in answering `OracleQueryMsg::Price` with prices set by its admin, so the
+//! oracle mode can be tested without a real price feed.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult,
+                   to_binary};
+use cw_multi_test::{App, AppResponse, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+use crate::msg::{OracleQueryMsg, PriceResp};
+use super::TestError;
+
+const ADMIN: Item<Addr> = Item::new("admin");
+const PRICES: Map<&str, PriceResp> = Map::new("prices");
+
+#[cw_serde]
+pub enum ExecMsg {
+    /// Sets the price of `denom`, updated at the current block time
+    SetPrice { denom: String, price: Decimal },
+}
+
+fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    ADMIN.save(deps.storage, &info.sender)?;
+    Ok(Response::new())
+}
+
+fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecMsg) -> StdResult<Response> {
+    match msg {
+        ExecMsg::SetPrice { denom, price } => {
+            if info.sender != ADMIN.load(deps.storage)? {
+                return Err(StdError::generic_err("Only the admin can set prices"));
+            }
+
+            PRICES.save(deps.storage, &denom, &PriceResp { price, updated_at: env.block.time })?;
+        }
+    }
+
+    Ok(Response::new())
+}
+
+fn query(deps: Deps, _env: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+    match msg {
+        OracleQueryMsg::Price { denom } => to_binary(&PRICES.load(deps.storage, &denom)?),
+    }
+}
+
+pub struct OracleContract(Addr);
+
+impl OracleContract {
+    pub fn addr(&self) -> &Addr {
+        &self.0
+    }
+
+    pub fn store_code(app: &mut App) -> u64 {
+        let contract = ContractWrapper::new(execute, instantiate, query);
+        app.store_code(Box::new(contract))
+    }
+
+    #[track_caller]
+    pub fn instantiate(app: &mut App, code_id: u64, sender: &Addr) -> Result<Self, TestError> {
+        app.instantiate_contract(
+            code_id,
+            sender.clone(),
+            &Empty {},
+            &[],
+            "Price oracle",
+            None,
+        ).map_err(TestError::from).map(OracleContract)
+    }
+
+    #[track_caller]
+    pub fn set_price(&self, app: &mut App, sender: &Addr, denom: &str, price: Decimal) -> Result<AppResponse, TestError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::SetPrice { denom: denom.to_owned(), price },
+            &[],
+        ).map_err(TestError::from)
+    }
+}
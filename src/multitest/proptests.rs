@@ -0,0 +1,181 @@
+use cosmwasm_std::{Addr, Coin, coin, coins, Decimal, from_slice};
+use counting_contract_0_1_0::multitest::CountingContract as CountingContract0_1_0;
+use cw_multi_test::App;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use crate::error::ContractError;
+use crate::msg::{ForwardMode, MigrateMsg, Parent, ParentPayout};
+use crate::multitest::{CountingContract, TestError};
+use crate::state::{DEFAULT_COUNTER, STATE};
+
+const ATOM: &str = "atom";
+const DENOMS: &[&str] = &[ATOM, "osmo"];
+const INITIAL_BALANCE: u128 = 1_000_000;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Donate(Vec<Coin>),
+    Reset(u64),
+    Withdraw,
+    /// Migration to the same version, only switching the parent payout
+    Migrate(ParentPayout),
+}
+
+fn funds() -> impl Strategy<Value = Vec<Coin>> {
+    proptest::sample::subsequence(DENOMS.to_vec(), 0..=DENOMS.len())
+        .prop_flat_map(|denoms| {
+            let amounts = vec(1u128..30, denoms.len());
+            (Just(denoms), amounts)
+        })
+        .prop_map(|(denoms, amounts)| {
+            denoms.into_iter().zip(amounts).map(|(denom, amount)| coin(amount, denom)).collect()
+        })
+}
+
+/// Parent configuration as `(donating_period, part percent)`.
+fn parent_config() -> impl Strategy<Value = Option<(u64, u64)>> {
+    proptest::option::of((1u64..5, 0u64..=100))
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        6 => funds().prop_map(Op::Donate),
+        1 => (0u64..100).prop_map(Op::Reset),
+        1 => Just(Op::Withdraw),
+        1 => prop_oneof![Just(ParentPayout::Push), Just(ParentPayout::Pull)].prop_map(Op::Migrate),
+    ]
+}
+
+fn parent(addr: &Addr, config: Option<(u64, u64)>) -> Option<Parent> {
+    config.map(|(donating_period, part)| Parent {
+        addr: addr.to_string(),
+        donating_period,
+        part: Decimal::percent(part),
+        mode: ForwardMode::Period,
+        payout: ParentPayout::Push,
+    })
+}
+
+fn totals(app: &App, addrs: &[&Addr]) -> Vec<u128> {
+    DENOMS.iter()
+        .map(|denom| {
+            addrs.iter()
+                .map(|addr| app.wrap().query_balance(*addr, *denom).unwrap().amount.u128())
+                .sum()
+        })
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// With `baseline_donations`, the contract starts on the 0.1.0 code, receives the donations
+    /// and is then migrated, configuring the parent on the way.
+    #[test]
+    fn donation_invariants(
+        config in parent_config(),
+        minimal_donation in 0u128..20,
+        baseline_donations in proptest::option::of(vec(1u128..30, 0..5)),
+        ops in vec(op(), 1..30),
+    ) {
+        let owner = Addr::unchecked("owner");
+        let admin = Addr::unchecked("admin");
+        let sender = Addr::unchecked("sender");
+
+        let mut app = App::new(|router, _api, storage| {
+            let balance = DENOMS.iter().map(|denom| coin(INITIAL_BALANCE, *denom)).collect();
+            router.bank.init_balance(storage, &sender, balance).unwrap();
+        });
+
+        let code_id = CountingContract::store_code(&mut app);
+        let baseline_code_id = CountingContract0_1_0::store_code(&mut app);
+
+        let parent_contract = CountingContract::builder(&mut app, code_id, &owner, coin(0, ATOM))
+            .call()
+            .unwrap();
+
+        let initial_totals = totals(&app, &[&sender, parent_contract.addr(), &owner]);
+
+        let contract = match baseline_donations {
+            Some(donations) => {
+                let contract = CountingContract0_1_0::instantiate(
+                    &mut app, baseline_code_id, &owner, Some(&admin), "Counting contract", coin(minimal_donation, ATOM),
+                ).unwrap();
+
+                for amount in donations {
+                    contract.donate(&mut app, &sender, &coins(amount, ATOM)).unwrap();
+                }
+
+                let counter: u64 = from_slice(&app.wrap().query_wasm_raw(contract.addr(), b"counter").unwrap().unwrap()).unwrap();
+
+                let contract = CountingContract::migrate(
+                    &mut app,
+                    &admin,
+                    contract.addr(),
+                    code_id,
+                    parent(parent_contract.addr(), config),
+                ).unwrap();
+
+                let state = STATE.query(&app.wrap(), contract.addr().clone(), DEFAULT_COUNTER).unwrap().unwrap();
+                prop_assert_eq!(state.counter, counter);
+                prop_assert_eq!(state.minimal_donation, coin(minimal_donation, ATOM));
+                contract
+            }
+            None => {
+                let mut builder = CountingContract::builder(&mut app, code_id, &owner, coin(minimal_donation, ATOM))
+                    .with_admin(&admin)
+                    .with_counter(0);
+
+                if let Some(parent) = parent(parent_contract.addr(), config) {
+                    builder = builder.with_parent(parent);
+                }
+
+                builder.call().unwrap()
+            }
+        };
+
+        let addrs = [&sender, contract.addr(), parent_contract.addr(), &owner];
+        prop_assert_eq!(totals(&app, &addrs), initial_totals.clone());
+
+        for op in ops {
+            let before = STATE.query(&app.wrap(), contract.addr().clone(), DEFAULT_COUNTER).unwrap().unwrap();
+
+            match op {
+                Op::Donate(funds) => {
+                    let counted = minimal_donation == 0 || funds.iter()
+                        .any(|coin| coin.denom == ATOM && coin.amount.u128() >= minimal_donation);
+
+                    prop_assert!(contract.donate(&mut app, &sender, &funds).is_ok());
+
+                    let after = STATE.query(&app.wrap(), contract.addr().clone(), DEFAULT_COUNTER).unwrap().unwrap();
+                    prop_assert_eq!(after.counter, before.counter + counted as u64);
+                }
+                Op::Reset(counter) => {
+                    contract.reset(&mut app, &owner, counter).unwrap();
+                    prop_assert_eq!(contract.query_value(&app).unwrap().value, counter);
+                }
+                Op::Withdraw => {
+                    match contract.withdraw(&mut app, &owner) {
+                        Ok(_) => (),
+                        Err(err) => prop_assert_eq!(err, TestError::Contract(ContractError::NothingToWithdraw)),
+                    }
+                }
+                Op::Migrate(payout) => {
+                    let msg = MigrateMsg { parent: None, parent_payout: Some(payout) };
+                    CountingContract::migrate_with(&mut app, &admin, contract.addr(), code_id, &msg).unwrap();
+                }
+            }
+
+            let state = STATE.query(&app.wrap(), contract.addr().clone(), DEFAULT_COUNTER).unwrap().unwrap();
+            match config {
+                Some((period, _)) => {
+                    let countdown = state.donating_parent.unwrap();
+                    prop_assert!((1..=period).contains(&countdown));
+                }
+                None => prop_assert_eq!(state.donating_parent, None),
+            }
+
+            prop_assert_eq!(totals(&app, &addrs), initial_totals.clone());
+        }
+    }
+}
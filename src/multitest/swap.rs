@@ -0,0 +1,106 @@
+//! DEX stand-in swapping coins at rates set by its admin. Simulations use the quoted rate while
+//! swaps pay out at the actual one, so slippage can be tested. Liquidity has to be given to the
+//! contract upfront.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, BankMsg, Binary, coins, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError,
+                   StdResult, to_binary, Uint128};
+use cw_multi_test::{App, AppResponse, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+use crate::msg::{SimulationResp, SwapQueryMsg};
+use super::TestError;
+
+const ADMIN: Item<Addr> = Item::new("admin");
+const RATES: Map<(&str, &str), Rate> = Map::new("rates");
+
+#[cw_serde]
+struct Rate {
+    quote: Decimal,
+    actual: Decimal,
+}
+
+#[cw_serde]
+pub enum ExecMsg {
+    /// Sets the rates of `offer_denom` in `ask_denom`
+    SetRate { offer_denom: String, ask_denom: String, quote: Decimal, actual: Decimal },
+    /// Same as `SwapExecMsg::Swap`
+    Swap { ask_denom: String, min_output: Uint128, recipient: Option<String> },
+}
+
+fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    ADMIN.save(deps.storage, &info.sender)?;
+    Ok(Response::new())
+}
+
+fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecMsg) -> StdResult<Response> {
+    match msg {
+        ExecMsg::SetRate { offer_denom, ask_denom, quote, actual } => {
+            if info.sender != ADMIN.load(deps.storage)? {
+                return Err(StdError::generic_err("Only the admin can set rates"));
+            }
+
+            RATES.save(deps.storage, (&offer_denom, &ask_denom), &Rate { quote, actual })?;
+            Ok(Response::new())
+        }
+        ExecMsg::Swap { ask_denom, min_output, recipient } => {
+            let offer = match info.funds.as_slice() {
+                [offer] => offer,
+                _ => return Err(StdError::generic_err("Exactly one coin has to be offered")),
+            };
+
+            let rate = RATES.load(deps.storage, (&offer.denom, &ask_denom))?;
+            let output = offer.amount * rate.actual;
+            if output < min_output {
+                return Err(StdError::generic_err("Output below the minimum"));
+            }
+
+            let recipient = recipient.unwrap_or_else(|| info.sender.into_string());
+            Ok(Response::new().add_message(BankMsg::Send { to_address: recipient, amount: coins(output.u128(), ask_denom) }))
+        }
+    }
+}
+
+fn query(deps: Deps, _env: Env, msg: SwapQueryMsg) -> StdResult<Binary> {
+    match msg {
+        SwapQueryMsg::Simulate { offer, ask_denom } => {
+            let rate = RATES.load(deps.storage, (&offer.denom, &ask_denom))?;
+            to_binary(&SimulationResp { amount: offer.amount * rate.quote })
+        }
+    }
+}
+
+pub struct SwapContract(Addr);
+
+impl SwapContract {
+    pub fn addr(&self) -> &Addr {
+        &self.0
+    }
+
+    pub fn store_code(app: &mut App) -> u64 {
+        let contract = ContractWrapper::new(execute, instantiate, query);
+        app.store_code(Box::new(contract))
+    }
+
+    #[track_caller]
+    pub fn instantiate(app: &mut App, code_id: u64, sender: &Addr) -> Result<Self, TestError> {
+        app.instantiate_contract(
+            code_id,
+            sender.clone(),
+            &Empty {},
+            &[],
+            "Swap",
+            None,
+        ).map_err(TestError::from).map(SwapContract)
+    }
+
+    #[track_caller]
+    pub fn set_rate(&self, app: &mut App, sender: &Addr, offer_denom: &str, ask_denom: &str, quote: Decimal, actual: Decimal)
+        -> Result<AppResponse, TestError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::SetRate { offer_denom: offer_denom.to_owned(), ask_denom: ask_denom.to_owned(), quote, actual },
+            &[],
+        ).map_err(TestError::from)
+    }
+}
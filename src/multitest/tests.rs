@@ -1,10 +1,16 @@
-use cosmwasm_std::{Addr, Coin, coins, Decimal, Empty};
+use cosmwasm_std::{Addr, Coin, coin, coins, Decimal, Empty, Event, to_binary, to_vec, Uint128};
 use crate::{execute, query, instantiate, ContractError};
-use crate::multitest::CountingContract;
+use crate::multitest::{CountingContract, TestError};
+use crate::multitest::fixtures::{self, ChildConfig};
+use crate::multitest::nft::NftContract;
+use crate::multitest::oracle::OracleContract;
+use crate::multitest::swap::SwapContract;
 use counting_contract_0_1_0::multitest::CountingContract as CountingContract0_1_0;
 use cw_multi_test::{App, Contract, ContractWrapper};
-use crate::msg::Parent;
-use crate::state::{STATE, State};
+use crate::msg::{AdminMsg, CampaignRelease, CampaignStatus, ChildResp, DonorMode, ExecMsg, MigrateMsg, FeeConfig, FeeMode, ForwardMode, MilestoneAction, ParentPayout,
+                 GovernanceConfig, HistoryEntry, MilestoneResp, OracleConfig, ScheduledMinimalDonation, Parent, ProposalAction, ProposalStatus, ReceiptExtension,
+                 ReceiptMode, ReceiptResp, SudoMsg, SwapConfig, SwapTrigger, VestingSchedule, VoteOption, VoteResp, Voucher, VoucherSigner};
+use crate::state::{DEFAULT_COUNTER, STATE, State};
 
 fn counting_contract() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(execute, instantiate, query);
@@ -84,7 +90,7 @@ fn unauthorized_withdraw() {
         .withdraw(&mut app, &member)
         .unwrap_err();
 
-    assert_eq!(err, ContractError::Unauthorized { owner: owner.into() })
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.into() }))
 }
 
 #[test]
@@ -113,8 +119,8 @@ fn migration() {
     let resp = contract.query_value(&app).unwrap();
     assert_eq!(resp.value, 0);
 
-    let state = STATE.query(&app.wrap(), contract.addr().clone()).unwrap();
-    assert_eq!(state, State { counter: 1, minimal_donation: Coin::new(10, ATOM), donating_parent: None })
+    let state = STATE.query(&app.wrap(), contract.addr().clone(), DEFAULT_COUNTER).unwrap();
+    assert_eq!(state, Some(State { counter: 1, minimal_donation: Coin::new(10, ATOM), donating_parent: None, owner: None }))
 
 }
 
@@ -177,6 +183,8 @@ fn donating_parent() {
             addr: contract_parent.addr().to_string(),
             donating_period: 2,
             part: Decimal::percent(10),
+            mode: ForwardMode::Period,
+            payout: ParentPayout::Push,
         }),
     ).unwrap();
 
@@ -188,3 +196,1769 @@ fn donating_parent() {
     assert_eq!(app.wrap().query_all_balances(contract_parent.addr()).unwrap(), coins(2, ATOM));
     assert_eq!(app.wrap().query_all_balances(owner.clone()).unwrap(), vec![]);
 }
+
+#[test]
+fn donate_events() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(10, ATOM)).unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(0, ATOM))
+        .with_counter(5)
+        .call()
+        .unwrap();
+
+    let resp = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+
+    assert!(resp.has_event(
+        &Event::new("wasm")
+            .add_attribute("action", "donate")
+            .add_attribute("sender", sender.as_str())
+            .add_attribute("counter", "6")
+    ));
+    assert_eq!(contract.query_incremented(&app, 6).unwrap().value, 7);
+}
+
+#[test]
+fn tree_fixture() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(20, ATOM)).unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let tree = fixtures::tree(
+        &mut app,
+        code_id,
+        &owner,
+        Coin::new(0, ATOM),
+        &[
+            ChildConfig { donating_period: 1, part: Decimal::percent(50) },
+            ChildConfig { donating_period: 1, part: Decimal::percent(10) },
+        ],
+    ).unwrap();
+
+    tree.children[0].donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    tree.children[1].donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+
+    assert_eq!(app.wrap().query_all_balances(tree.children[0].addr()).unwrap(), coins(5, ATOM));
+    assert_eq!(app.wrap().query_all_balances(tree.children[1].addr()).unwrap(), coins(9, ATOM));
+    assert_eq!(app.wrap().query_all_balances(tree.root.addr()).unwrap(), coins(6, ATOM));
+    assert_eq!(tree.root.query_value(&app).unwrap().value, 3);
+}
+
+#[test]
+fn invalid_parent_config() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Parent contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+
+    let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: parent.addr().to_string(), donating_period: 0, part: Decimal::percent(10), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidPeriod));
+
+    let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: parent.addr().to_string(), donating_period: 1, part: Decimal::percent(150), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidPart(Decimal::percent(150))));
+
+    // The parent took `contract0`, so the next contract instantiated is `contract1`
+    let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: "contract1".to_owned(), donating_period: 1, part: Decimal::percent(10), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::SelfParent));
+}
+
+#[test]
+fn invalid_denom() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+    let code_id = CountingContract::store_code(&mut app);
+
+    for denom in ["", "at", "1atom", "at om"] {
+        let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, denom))
+            .call()
+            .unwrap_err();
+        assert_eq!(err, TestError::Contract(ContractError::InvalidDenom(denom.to_owned())));
+    }
+}
+
+#[test]
+fn query_parent() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+    let code_id = CountingContract::store_code(&mut app);
+
+    let link = ChildConfig { donating_period: 3, part: Decimal::percent(20) };
+    let chain = fixtures::chain(&mut app, code_id, &owner, Coin::new(10, ATOM), 2, link).unwrap();
+
+    assert_eq!(chain[0].query_parent(&app).unwrap().parent, None);
+    assert_eq!(
+        chain[1].query_parent(&app).unwrap().parent,
+        Some(Parent { addr: chain[0].addr().to_string(), donating_period: 3, part: Decimal::percent(20), mode: ForwardMode::Period, payout: ParentPayout::Push })
+    );
+}
+
+#[test]
+fn parent_chain_too_deep() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+    let code_id = CountingContract::store_code(&mut app);
+
+    let link = ChildConfig { donating_period: 1, part: Decimal::percent(10) };
+    let chain = fixtures::chain(&mut app, code_id, &owner, Coin::new(10, ATOM), 3, link).unwrap();
+
+    let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_max_parent_depth(2)
+        .with_parent(Parent { addr: chain[2].addr().to_string(), donating_period: 1, part: Decimal::percent(10), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap_err();
+
+    assert_eq!(err, TestError::Contract(ContractError::ParentDepthExceeded { max_depth: 2 }));
+}
+
+#[test]
+fn parent_cycle_through_migration() {
+    let owner = Addr::unchecked("owner");
+    let admin = Addr::unchecked("admin");
+
+    let mut app = App::default();
+    let old_code_id = CountingContract0_1_0::store_code(&mut app);
+    let code_id = CountingContract::store_code(&mut app);
+
+    let old_parent = CountingContract0_1_0::instantiate(
+        &mut app, old_code_id, &owner, Some(&admin), "Counting contract", Coin::new(10, ATOM),
+    ).unwrap();
+
+    // The old parent doesn't know about parents, so the chain ends with it
+    let child = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: old_parent.addr().to_string(), donating_period: 1, part: Decimal::percent(10), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap();
+
+    let err = CountingContract::migrate(
+        &mut app,
+        &admin,
+        old_parent.addr(),
+        code_id,
+        Some(Parent { addr: child.addr().to_string(), donating_period: 1, part: Decimal::percent(10), mode: ForwardMode::Period, payout: ParentPayout::Push }),
+    ).unwrap_err();
+
+    assert_eq!(err, TestError::Contract(ContractError::ParentCycle));
+}
+
+#[test]
+fn legacy_parent() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(10, ATOM)).unwrap();
+    });
+    let old_code_id = CountingContract0_1_0::store_code(&mut app);
+    let code_id = CountingContract::store_code(&mut app);
+
+    // Only counting contracts can be parents
+    let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: sender.to_string(), donating_period: 1, part: Decimal::percent(50), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap_err();
+    assert!(matches!(err, TestError::Contract(ContractError::Std(_))));
+
+    let old_parent = CountingContract0_1_0::instantiate(
+        &mut app, old_code_id, &owner, None, "Counting contract", Coin::new(5, ATOM),
+    ).unwrap();
+
+    let child = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: old_parent.addr().to_string(), donating_period: 1, part: Decimal::percent(50), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap();
+
+    // The old parent only takes a plain donation
+    child.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert_eq!(app.wrap().query_all_balances(old_parent.addr()).unwrap(), coins(5, ATOM));
+    assert_eq!(app.wrap().query_all_balances(child.addr()).unwrap(), coins(5, ATOM));
+
+    assert_eq!(child.query_ancestors(&app).unwrap().ancestors, vec![old_parent.addr().to_string()]);
+}
+
+#[test]
+fn forwarded_donation_depth_guard() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(10, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    // The parent doesn't accept donations forwarded by any child
+    let parent = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_max_parent_depth(0)
+        .call()
+        .unwrap();
+
+    let child = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: parent.addr().to_string(), donating_period: 1, part: Decimal::percent(10), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .call()
+        .unwrap();
+
+    parent.set_child_approval(&mut app, &owner, child.addr(), true).unwrap();
+
+    let err = child.donate(&mut app, &sender, &coins(10, ATOM)).unwrap_err();
+
+    assert_eq!(err, TestError::Contract(ContractError::ParentDepthExceeded { max_depth: 0 }));
+    assert_eq!(app.wrap().query_all_balances(&sender).unwrap(), coins(10, ATOM));
+}
+
+#[test]
+fn donation_tree() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(30, ATOM)).unwrap();
+    });
+
+    let code_id = CountingContract::store_code(&mut app);
+
+    let tree = fixtures::tree(
+        &mut app,
+        code_id,
+        &owner,
+        Coin::new(10, ATOM),
+        &[
+            ChildConfig { donating_period: 1, part: Decimal::percent(50) },
+            ChildConfig { donating_period: 2, part: Decimal::percent(50) },
+        ],
+    ).unwrap();
+
+    tree.children[0].donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    tree.children[0].donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    tree.children[1].donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+
+    let mut children = tree.root.query_children(&app, None, None).unwrap().children;
+    children.sort_by(|a, b| a.addr.cmp(&b.addr));
+
+    assert_eq!(children, vec![
+        ChildResp { addr: tree.children[0].addr().to_string(), received: coins(12, ATOM), approved: false },
+        ChildResp { addr: tree.children[1].addr().to_string(), received: vec![], approved: false },
+    ]);
+
+    let page = tree.root.query_children(&app, Some(tree.children[0].addr()), Some(1)).unwrap();
+    assert_eq!(page.children.len(), 1);
+    assert_eq!(page.children[0].addr, tree.children[1].addr().to_string());
+
+    assert_eq!(
+        tree.children[1].query_ancestors(&app).unwrap().ancestors,
+        vec![tree.root.addr().to_string()]
+    );
+}
+
+#[test]
+fn register_non_child() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+
+    let err = contract.register_child(&mut app, &owner).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NotAChild { addr: owner.to_string() }));
+}
+
+#[test]
+fn internal_receipts() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(30, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+
+    let err = contract.set_receipt_mode(&mut app, &sender, Some(ReceiptMode::Internal {})).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    // Donations before receipts are enabled and uncounted donations don't get a receipt
+    contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    contract.set_receipt_mode(&mut app, &owner, Some(ReceiptMode::Internal {})).unwrap();
+    contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap();
+    let resp = contract.donate(&mut app, &sender, &coins(15, ATOM)).unwrap();
+
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("receipt_id", "0")));
+
+    let receipts = contract.query_receipts(&app, &sender, None, None).unwrap().receipts;
+    assert_eq!(receipts, vec![ReceiptResp {
+        id: 0,
+        owner: sender.to_string(),
+        counter: 3,
+        amount: coins(15, ATOM),
+        time: app.block_info().time,
+    }]);
+    assert_eq!(contract.query_receipt(&app, 0).unwrap(), receipts[0]);
+    assert!(contract.query_receipts(&app, &owner, None, None).unwrap().receipts.is_empty());
+}
+
+#[test]
+fn external_receipts() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(20, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+    let nft_code_id = NftContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+    let nft = NftContract::instantiate(&mut app, nft_code_id, &owner, contract.addr()).unwrap();
+
+    contract.set_receipt_mode(
+        &mut app,
+        &owner,
+        Some(ReceiptMode::External { nft_contract: nft.addr().to_string() }),
+    ).unwrap();
+
+    contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+
+    assert_eq!(nft.query_num_tokens(&app).unwrap().count, 2);
+
+    let token = nft.query_token(&app, "1").unwrap();
+    assert_eq!(token.owner, sender);
+    assert_eq!(token.extension, ReceiptExtension { counter: 3, amount: coins(10, ATOM), time: app.block_info().time });
+    assert_eq!(contract.query_receipts(&app, &sender, Some(0), None).unwrap().receipts.len(), 1);
+}
+
+#[test]
+fn milestone_bonus() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let lucky = Addr::unchecked("lucky");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+        router.bank.init_balance(storage, &lucky, coins(10, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+
+    let err = contract
+        .add_milestone(&mut app, &owner, 1, MilestoneAction::Event {})
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidMilestone { counter: 1 }));
+
+    contract.add_milestone(&mut app, &owner, 3, MilestoneAction::Bonus { amount: coins(15, ATOM) }).unwrap();
+    contract.add_milestone(&mut app, &owner, 10, MilestoneAction::Event {}).unwrap();
+
+    contract.donate(&mut app, &sender, &coins(50, ATOM)).unwrap();
+    let resp = contract.donate(&mut app, &lucky, &coins(10, ATOM)).unwrap();
+
+    assert!(resp.has_event(
+        &Event::new("wasm-milestone")
+            .add_attribute("counter", "3")
+            .add_attribute("donor", lucky.as_str())
+    ));
+    assert_eq!(app.wrap().query_all_balances(&lucky).unwrap(), coins(15, ATOM));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), coins(45, ATOM));
+
+    assert_eq!(contract.query_achieved_milestones(&app, None, None).unwrap().milestones, vec![MilestoneResp {
+        counter: 3,
+        action: MilestoneAction::Bonus { amount: coins(15, ATOM) },
+        achieved_by: Some(lucky.to_string()),
+        achieved_at: Some(app.block_info().height),
+    }]);
+    assert_eq!(contract.query_upcoming_milestones(&app, None, None).unwrap().milestones, vec![MilestoneResp {
+        counter: 10,
+        action: MilestoneAction::Event {},
+        achieved_by: None,
+        achieved_at: None,
+    }]);
+
+    // Resetting below an achieved milestone doesn't trigger it again
+    contract.reset(&mut app, &owner, 2).unwrap();
+    let resp = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert!(!resp.events.iter().any(|event| event.ty == "wasm-milestone"));
+
+    // Nobody else can reset right below a milestone to collect it
+    let err = contract.reset(&mut app, &sender, 9).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+    let resp = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert!(!resp.events.iter().any(|event| event.ty == "wasm-milestone"));
+    assert_eq!(contract.query_value(&app).unwrap().value, 4);
+
+    // Milestones skipped by a reset are marked as achieved without performing their actions
+    contract.add_milestone(&mut app, &owner, 11, MilestoneAction::Bonus { amount: coins(15, ATOM) }).unwrap();
+    let resp = contract.reset(&mut app, &owner, 12).unwrap();
+    assert!(!resp.events.iter().any(|event| event.ty == "wasm-milestone"));
+    assert!(resp.has_event(&Event::new("wasm-milestone_skipped").add_attribute("counter", "10")));
+    assert!(resp.has_event(&Event::new("wasm-milestone_skipped").add_attribute("counter", "11")));
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), vec![]);
+    assert!(contract.query_upcoming_milestones(&app, None, None).unwrap().milestones.is_empty());
+}
+
+#[test]
+fn milestone_notify() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(10, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+    let notified = CountingContract::builder(&mut app, code_id, &owner, Coin::new(0, ATOM))
+        .with_counter(0)
+        .call()
+        .unwrap();
+
+    contract.add_milestone(&mut app, &owner, 2, MilestoneAction::Notify {
+        contract: notified.addr().to_string(),
+        msg: to_binary(&ExecMsg::Donate { depth: None, counter_id: None }).unwrap(),
+    }).unwrap();
+    contract.add_milestone(&mut app, &owner, 5, MilestoneAction::Event {}).unwrap();
+    contract.remove_milestone(&mut app, &owner, 5).unwrap();
+
+    contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+
+    assert_eq!(notified.query_value(&app).unwrap().value, 1);
+    assert!(contract.query_upcoming_milestones(&app, None, None).unwrap().milestones.is_empty());
+}
+
+#[test]
+fn matching_pool() {
+    let owner = Addr::unchecked("owner");
+    let sponsor = Addr::unchecked("sponsor");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+        router.bank.init_balance(storage, &sponsor, coins(50, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+
+    let expires_at = app.block_info().time.plus_seconds(100);
+
+    let err = contract
+        .fund_matching_pool(&mut app, &sponsor, &[], Decimal::percent(50), None, expires_at)
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidMatchingFunds));
+
+    contract.fund_matching_pool(
+        &mut app,
+        &sponsor,
+        &coins(50, ATOM),
+        Decimal::percent(50),
+        Some(Uint128::new(15)),
+        expires_at,
+    ).unwrap();
+
+    let resp = contract.donate(&mut app, &sender, &coins(20, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("matched", "10atom")));
+    contract.donate(&mut app, &sender, &coins(20, ATOM)).unwrap();
+
+    // Donations below the minimum are not matched
+    contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap();
+
+    assert_eq!(contract.query_matched(&app, 0, &sender).unwrap().amount, Uint128::new(15));
+    let pool = contract.query_matching_pool(&app).unwrap().pool.unwrap();
+    assert_eq!(pool.remaining, coin(35, ATOM));
+
+    let err = contract.reclaim_matching_pool(&mut app, &sponsor).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::MatchingPoolActive { expires_at }));
+
+    // The owner only gets the donations and matched funds, the rest of the pool stays escrowed
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(60, ATOM));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+    let err = contract.reclaim_matching_pool(&mut app, &owner).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: sponsor.to_string() }));
+
+    contract.reclaim_matching_pool(&mut app, &sponsor).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&sponsor).unwrap(), coins(35, ATOM));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), vec![]);
+    assert_eq!(contract.query_matching_pool(&app).unwrap().pool, None);
+}
+
+#[test]
+fn successful_campaign() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+
+    let end = app.block_info().time.plus_seconds(100);
+
+    let err = contract
+        .start_campaign(&mut app, &owner, None, end, coins(30, ATOM), CampaignRelease::Parent {})
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NoParent));
+
+    contract.start_campaign(&mut app, &owner, None, end, coins(30, ATOM), CampaignRelease::Owner {}).unwrap();
+
+    contract.donate(&mut app, &sender, &coins(20, ATOM)).unwrap();
+    contract.donate(&mut app, &sender, &coins(15, ATOM)).unwrap();
+    // Below the minimal donation, so it doesn't take part in the campaign
+    contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap();
+
+    let err = contract.finalize_campaign(&mut app, &sender).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::CampaignNotEnded { end }));
+
+    // Campaign funds are escrowed until the campaign is finalized
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(5, ATOM));
+
+    app.update_block(|block| block.time = end);
+    contract.finalize_campaign(&mut app, &sender).unwrap();
+
+    let campaign = contract.query_campaign(&app, None).unwrap();
+    assert_eq!(campaign.status, CampaignStatus::Succeeded);
+    assert_eq!(campaign.raised, coins(35, ATOM));
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(40, ATOM));
+
+    let err = contract.claim_refund(&mut app, &sender, 0).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::CampaignNotFailed { id: 0 }));
+}
+
+#[test]
+fn failed_campaign_refunds() {
+    let owner = Addr::unchecked("owner");
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &first, coins(20, ATOM)).unwrap();
+        router.bank.init_balance(storage, &second, coins(20, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::instantiate(
+        &mut app, code_id, &owner, None, "Counting contract", Coin::new(10, ATOM), None,
+    ).unwrap();
+
+    let start = app.block_info().time.plus_seconds(10);
+    let end = start.plus_seconds(100);
+    contract.start_campaign(&mut app, &owner, Some(start), end, coins(100, ATOM), CampaignRelease::Owner {}).unwrap();
+
+    // Not started yet
+    contract.donate(&mut app, &first, &coins(10, ATOM)).unwrap();
+
+    app.update_block(|block| block.time = start);
+    contract.donate(&mut app, &first, &coins(10, ATOM)).unwrap();
+    contract.donate(&mut app, &second, &coins(20, ATOM)).unwrap();
+
+    assert_eq!(contract.query_campaign_contribution(&app, 0, &second).unwrap().amount, coins(20, ATOM));
+
+    app.update_block(|block| block.time = end);
+    contract.finalize_campaign(&mut app, &owner).unwrap();
+    assert_eq!(contract.query_campaign(&app, Some(0)).unwrap().status, CampaignStatus::Failed);
+
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(10, ATOM));
+
+    contract.claim_refund(&mut app, &first, 0).unwrap();
+    contract.claim_refund(&mut app, &second, 0).unwrap();
+
+    assert_eq!(app.wrap().query_all_balances(&first).unwrap(), coins(10, ATOM));
+    assert_eq!(app.wrap().query_all_balances(&second).unwrap(), coins(20, ATOM));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), vec![]);
+
+    let err = contract.claim_refund(&mut app, &second, 0).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NothingToRefund));
+}
+
+#[test]
+fn linear_vesting() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(200, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let start = app.block_info().time.plus_seconds(10);
+    let end = start.plus_seconds(100);
+
+    let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_vesting(VestingSchedule::Linear { start: end, end: start })
+        .call()
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidVesting));
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_vesting(VestingSchedule::Linear { start, end })
+        .call()
+        .unwrap();
+
+    contract.donate(&mut app, &sender, &coins(100, ATOM)).unwrap();
+
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NothingToWithdraw));
+
+    app.update_block(|block| block.time = start.plus_seconds(25));
+
+    let vesting = contract.query_vesting(&app).unwrap();
+    assert_eq!(vesting.unlocked, coins(25, ATOM));
+    assert_eq!(vesting.withdrawable, coins(25, ATOM));
+
+    let err = contract.withdraw_amount(&mut app, &owner, &coins(30, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::ExceedsVested { available: coin(25, ATOM) }));
+
+    contract.withdraw_amount(&mut app, &owner, &coins(10, ATOM)).unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(25, ATOM));
+
+    // Later donations vest on the same schedule
+    contract.donate(&mut app, &sender, &coins(100, ATOM)).unwrap();
+    app.update_block(|block| block.time = start.plus_seconds(50));
+
+    let vesting = contract.query_vesting(&app).unwrap();
+    assert_eq!(vesting.unlocked, coins(100, ATOM));
+    assert_eq!(vesting.withdrawn, coins(25, ATOM));
+    assert_eq!(vesting.withdrawable, coins(75, ATOM));
+
+    app.update_block(|block| block.time = end);
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(200, ATOM));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), vec![]);
+}
+
+#[test]
+fn cliff_vesting() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(50, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let at = app.block_info().time.plus_seconds(100);
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_vesting(VestingSchedule::Cliff { at })
+        .call()
+        .unwrap();
+
+    contract.donate(&mut app, &sender, &coins(50, ATOM)).unwrap();
+
+    app.update_block(|block| block.time = at.minus_seconds(1));
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NothingToWithdraw));
+
+    app.update_block(|block| block.time = at);
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(50, ATOM));
+}
+
+#[test]
+fn immediate_fee() {
+    let owner = Addr::unchecked("owner");
+    let operator = Addr::unchecked("operator");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(210, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let fee = FeeConfig { recipient: operator.to_string(), rate: Decimal::one(), mode: FeeMode::Immediate {} };
+    let err = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_fee(fee)
+        .call()
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidFeeRate(Decimal::one())));
+
+    let fee = FeeConfig { recipient: operator.to_string(), rate: Decimal::percent(10), mode: FeeMode::Immediate {} };
+    let parent = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_fee(fee.clone())
+        .call()
+        .unwrap();
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(Parent { addr: parent.addr().to_string(), donating_period: 1, part: Decimal::percent(50), mode: ForwardMode::Period, payout: ParentPayout::Push })
+        .with_fee(fee.clone())
+        .call()
+        .unwrap();
+    parent.set_child_approval(&mut app, &owner, contract.addr(), true).unwrap();
+
+    let resp = contract.donate(&mut app, &sender, &coins(100, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("fee", "10atom")));
+
+    // The parent share is taken from what is left after the fee, and isn't charged again by the parent
+    assert_eq!(app.wrap().query_all_balances(&operator).unwrap(), coins(10, ATOM));
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(45, ATOM));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), coins(45, ATOM));
+    assert_eq!(parent.query_config(&app).unwrap().fees_collected, vec![]);
+
+    let config = contract.query_config(&app).unwrap();
+    assert_eq!(config.fee, Some(fee));
+    assert_eq!(config.fees_collected, coins(10, ATOM));
+    assert_eq!(config.fees_accrued, vec![]);
+
+    let err = contract.claim_fees(&mut app, &operator).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NothingToClaim));
+
+    // What is left after the fee has to reach the minimal donation
+    let value = contract.query_value(&app).unwrap().value;
+    let resp = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("fee", "1atom")));
+    assert_eq!(contract.query_value(&app).unwrap().value, value);
+
+    // Only approved children can claim their donations were forwarded
+    let msg = ExecMsg::Donate { depth: Some(1), counter_id: None };
+    let resp = parent.execute(&mut app, &sender, &msg, &coins(100, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("fee", "10atom")));
+    assert_eq!(parent.query_config(&app).unwrap().fees_collected, coins(10, ATOM));
+}
+
+#[test]
+fn accrued_fee() {
+    let owner = Addr::unchecked("owner");
+    let operator = Addr::unchecked("operator");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(200, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_fee(FeeConfig { recipient: operator.to_string(), rate: Decimal::percent(5), mode: FeeMode::Accrued {} })
+        .call()
+        .unwrap();
+
+    contract.donate(&mut app, &sender, &coins(100, ATOM)).unwrap();
+    contract.donate(&mut app, &sender, &coins(60, ATOM)).unwrap();
+
+    let config = contract.query_config(&app).unwrap();
+    assert_eq!(config.fees_collected, coins(8, ATOM));
+    assert_eq!(config.fees_accrued, coins(8, ATOM));
+
+    // Accrued fees are not part of the donations
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(152, ATOM));
+
+    let err = contract.claim_fees(&mut app, &owner).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: operator.to_string() }));
+
+    contract.claim_fees(&mut app, &operator).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&operator).unwrap(), coins(8, ATOM));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), vec![]);
+
+    let config = contract.query_config(&app).unwrap();
+    assert_eq!(config.fees_collected, coins(8, ATOM));
+    assert_eq!(config.fees_accrued, vec![]);
+}
+
+#[test]
+fn stream_forwarding() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(200, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent = CountingContract::builder(&mut app, code_id, &owner, Coin::new(1, ATOM))
+        .call()
+        .unwrap();
+
+    let config = Parent {
+        addr: parent.addr().to_string(),
+        donating_period: 3,
+        part: Decimal::percent(10),
+        mode: ForwardMode::Stream,
+        payout: ParentPayout::Push,
+    };
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_parent(config.clone())
+        .call()
+        .unwrap();
+
+    assert_eq!(contract.query_parent(&app).unwrap().parent, Some(config));
+
+    // Every donation is forwarded regardless of the period, based only on the donated coins
+    contract.donate(&mut app, &sender, &coins(100, ATOM)).unwrap();
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(10, ATOM));
+
+    contract.donate(&mut app, &sender, &coins(55, ATOM)).unwrap();
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(15, ATOM));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), coins(140, ATOM));
+
+    // Donations below the minimum are not forwarded
+    contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap();
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(15, ATOM));
+    assert_eq!(parent.query_value(&app).unwrap().value, 3);
+}
+
+#[test]
+fn donor_lists() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+
+    let mut app = App::new(|router, _api, storage| {
+        for donor in [&alice, &bob, &carol] {
+            router.bank.init_balance(storage, donor, coins(50, ATOM)).unwrap();
+        }
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+
+    let err = contract.update_denylist(&mut app, &alice, &[&bob], &[]).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    contract.update_denylist(&mut app, &owner, &[&bob, &carol], &[&carol]).unwrap();
+
+    contract.donate(&mut app, &alice, &coins(10, ATOM)).unwrap();
+    contract.donate(&mut app, &carol, &coins(10, ATOM)).unwrap();
+    let err = contract.donate(&mut app, &bob, &coins(10, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::DonorNotAllowed { donor: bob.to_string() }));
+
+    contract.set_donor_mode(&mut app, &owner, DonorMode::Allowlist {}).unwrap();
+    contract.update_allowlist(&mut app, &owner, &[&alice, &bob], &[]).unwrap();
+
+    contract.donate(&mut app, &alice, &coins(10, ATOM)).unwrap();
+    // The denylist takes precedence over the allowlist
+    let err = contract.donate(&mut app, &bob, &coins(10, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::DonorNotAllowed { donor: bob.to_string() }));
+    let err = contract.donate(&mut app, &carol, &coins(10, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::DonorNotAllowed { donor: carol.to_string() }));
+
+    assert_eq!(contract.query_value(&app).unwrap().value, 4);
+    assert_eq!(contract.query_config(&app).unwrap().donor_mode, DonorMode::Allowlist {});
+
+    let allowlist = contract.query_allowlist(&app, None, Some(1)).unwrap();
+    assert_eq!(allowlist.donors, vec![alice.to_string()]);
+    let allowlist = contract.query_allowlist(&app, Some(&alice), None).unwrap();
+    assert_eq!(allowlist.donors, vec![bob.to_string()]);
+    assert_eq!(contract.query_denylist(&app, None, None).unwrap().donors, vec![bob.to_string()]);
+}
+
+#[test]
+fn allowlist_accepts_children() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let tree = fixtures::tree(
+        &mut app, code_id, &owner, Coin::new(10, ATOM),
+        &[ChildConfig { donating_period: 1, part: Decimal::percent(50) }],
+    ).unwrap();
+
+    tree.root.set_donor_mode(&mut app, &owner, DonorMode::Allowlist {}).unwrap();
+
+    let err = tree.root.donate(&mut app, &sender, &coins(10, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::DonorNotAllowed { donor: sender.to_string() }));
+
+    // Registered children are only let through once the owner approves them
+    let child = tree.children[0].addr();
+    let err = tree.children[0].donate(&mut app, &sender, &coins(40, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::DonorNotAllowed { donor: child.to_string() }));
+
+    let err = tree.root.set_child_approval(&mut app, &sender, child, true).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+    let err = tree.root.set_child_approval(&mut app, &owner, &sender, true).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NotAChild { addr: sender.to_string() }));
+    tree.root.set_child_approval(&mut app, &owner, child, true).unwrap();
+
+    tree.children[0].donate(&mut app, &sender, &coins(40, ATOM)).unwrap();
+    assert_eq!(app.wrap().query_all_balances(tree.root.addr()).unwrap(), coins(20, ATOM));
+
+    tree.root.set_child_approval(&mut app, &owner, child, false).unwrap();
+    let err = tree.children[0].donate(&mut app, &sender, &coins(40, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::DonorNotAllowed { donor: child.to_string() }));
+}
+
+#[test]
+fn secp256k1_vouchers() {
+    use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+    let owner = Addr::unchecked("owner");
+    let relayer = Addr::unchecked("relayer");
+    let donor = Addr::unchecked("donor");
+
+    let mut app = App::default();
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+
+    let key = SigningKey::from_bytes(&[1; 32]).unwrap();
+    let sign = |voucher: &Voucher| {
+        let signature: Signature = key.sign(&to_vec(voucher).unwrap());
+        signature.as_ref().to_vec()
+    };
+
+    let voucher = Voucher {
+        contract: contract.addr().to_string(),
+        donor: donor.to_string(),
+        nonce: 0,
+        expires_at: app.block_info().time.plus_seconds(100),
+        credit: coins(25, ATOM),
+    };
+
+    let err = contract.redeem_voucher(&mut app, &relayer, voucher.clone(), &sign(&voucher)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NoVoucherSigner));
+
+    let pubkey = key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+    contract.set_voucher_signer(&mut app, &owner, Some(VoucherSigner::Secp256k1 { pubkey: pubkey.into() })).unwrap();
+
+    // Signed payload doesn't match the redeemed voucher
+    let forged = Voucher { credit: coins(100, ATOM), ..voucher.clone() };
+    let err = contract.redeem_voucher(&mut app, &relayer, forged, &sign(&voucher)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidSignature));
+
+    contract.redeem_voucher(&mut app, &relayer, voucher.clone(), &sign(&voucher)).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 2);
+    assert_eq!(contract.query_credits(&app, &donor).unwrap().amount, coins(25, ATOM));
+
+    let err = contract.redeem_voucher(&mut app, &relayer, voucher.clone(), &sign(&voucher)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::VoucherUsed { nonce: 0 }));
+
+    let expired = Voucher { nonce: 1, ..voucher.clone() };
+    app.update_block(|block| block.time = expired.expires_at);
+    let err = contract.redeem_voucher(&mut app, &relayer, expired.clone(), &sign(&expired)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::VoucherExpired { expires_at: expired.expires_at }));
+}
+
+#[test]
+fn ed25519_vouchers() {
+    use ed25519_zebra::{SigningKey, VerificationKey};
+
+    let owner = Addr::unchecked("owner");
+    let donor = Addr::unchecked("donor");
+
+    let mut app = App::default();
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+    let other = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+
+    let key = SigningKey::from([2; 32]);
+    let sign = |voucher: &Voucher| <[u8; 64]>::from(key.sign(&to_vec(voucher).unwrap())).to_vec();
+    let pubkey = VerificationKey::from(&key).as_ref().to_vec();
+
+    for contract in [&contract, &other] {
+        contract.set_voucher_signer(&mut app, &owner, Some(VoucherSigner::Ed25519 { pubkey: pubkey.clone().into() })).unwrap();
+    }
+
+    let voucher = Voucher {
+        contract: contract.addr().to_string(),
+        donor: donor.to_string(),
+        nonce: 7,
+        expires_at: app.block_info().time.plus_seconds(100),
+        credit: vec![],
+    };
+
+    // Vouchers can't be replayed on other contracts sharing the signer
+    let err = other.redeem_voucher(&mut app, &donor, voucher.clone(), &sign(&voucher)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidVoucherContract { contract: contract.addr().to_string() }));
+
+    contract.redeem_voucher(&mut app, &donor, voucher.clone(), &sign(&voucher)).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 2);
+    assert_eq!(contract.query_credits(&app, &donor).unwrap().amount, vec![]);
+}
+
+#[test]
+fn multiple_counters() {
+    let owner = Addr::unchecked("owner");
+    let manager = Addr::unchecked("manager");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent = CountingContract::builder(&mut app, code_id, &owner, Coin::new(0, ATOM))
+        .call()
+        .unwrap();
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+
+    let err = contract
+        .create_counter(&mut app, &manager, "events", Coin::new(5, ATOM), None, None)
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    let err = contract
+        .create_counter(&mut app, &owner, "no spaces", Coin::new(5, ATOM), None, None)
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidCounterId("no spaces".to_owned())));
+
+    let parent_config = Parent {
+        addr: parent.addr().to_string(),
+        donating_period: 1,
+        part: Decimal::percent(50),
+        mode: ForwardMode::Stream,
+        payout: ParentPayout::Push,
+    };
+    contract
+        .create_counter(&mut app, &owner, "events", Coin::new(5, ATOM), Some(&manager), Some(parent_config.clone()))
+        .unwrap();
+
+    let err = contract
+        .create_counter(&mut app, &owner, "events", Coin::new(5, ATOM), None, None)
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::CounterExists { id: "events".to_owned() }));
+
+    // Counted against the minimal donation of the chosen counter only
+    contract.donate_to(&mut app, &sender, "events", &coins(6, ATOM)).unwrap();
+    contract.donate(&mut app, &sender, &coins(6, ATOM)).unwrap();
+
+    let err = contract.donate_to(&mut app, &sender, "unknown", &coins(6, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::UnknownCounter { id: "unknown".to_owned() }));
+
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(3, ATOM));
+    assert_eq!(parent.query_value(&app).unwrap().value, 2);
+
+    let counter = contract.query_counter(&app, "events").unwrap();
+    assert_eq!(counter.value, 1);
+    assert_eq!(counter.owner, Some(manager.to_string()));
+    assert_eq!(counter.parent, Some(parent_config));
+
+    let err = contract.reset_counter(&mut app, &owner, "events", 10).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: manager.to_string() }));
+    contract.reset_counter(&mut app, &manager, "events", 10).unwrap();
+
+    let counters = contract.query_counters(&app, None, None).unwrap().counters;
+    let ids: Vec<_> = counters.iter().map(|counter| (counter.id.as_str(), counter.value)).collect();
+    assert_eq!(ids, vec![("default", 1), ("events", 10)]);
+
+    let counters = contract.query_counters(&app, Some("default"), None).unwrap().counters;
+    assert_eq!(counters.len(), 1);
+    assert_eq!(counters[0].id, "events");
+}
+
+#[test]
+fn value_history() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let start = app.block_info().height;
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+
+    for _ in 0..3 {
+        app.update_block(|block| block.height += 10);
+        contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+        contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    }
+
+    assert_eq!(contract.query_value_at(&app, start - 1).unwrap().value, None);
+    assert_eq!(contract.query_value_at(&app, start).unwrap().value, Some(1));
+    assert_eq!(contract.query_value_at(&app, start + 15).unwrap().value, Some(3));
+    assert_eq!(contract.query_value_at(&app, start + 30).unwrap().value, Some(7));
+
+    let history = contract.query_value_history(&app, Some(start), Some(2)).unwrap().history;
+    assert_eq!(history, vec![
+        HistoryEntry { height: start + 10, value: 3 },
+        HistoryEntry { height: start + 20, value: 5 },
+    ]);
+
+    let err = contract.set_history_retention(&mut app, &sender, Some(15)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    contract.set_history_retention(&mut app, &owner, Some(15)).unwrap();
+    let resp = contract.prune_history(&mut app, &sender, None).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("pruned", "1")));
+
+    // The value at the start of the retention window is still known
+    assert_eq!(contract.query_value_at(&app, start).unwrap().value, None);
+    assert_eq!(contract.query_value_at(&app, start + 15).unwrap().value, Some(3));
+    assert_eq!(contract.query_config(&app).unwrap().history_retention, Some(15));
+
+    // Further changes prune the history on their own
+    app.update_block(|block| block.height += 100);
+    contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+
+    let history = contract.query_value_history(&app, None, None).unwrap().history;
+    assert_eq!(history, vec![
+        HistoryEntry { height: start + 30, value: 7 },
+        HistoryEntry { height: start + 130, value: 8 },
+    ]);
+}
+
+#[test]
+fn governance() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &alice, coins(30, ATOM)).unwrap();
+        router.bank.init_balance(storage, &bob, coins(10, ATOM)).unwrap();
+        router.bank.init_balance(storage, &carol, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+
+    let action = ProposalAction::UpdateMinimalDonation { minimal_donation: Coin::new(20, ATOM) };
+    let err = contract.propose(&mut app, &alice, action.clone()).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::GovernanceDisabled));
+
+    let config = GovernanceConfig {
+        voting_period: 100,
+        quorum: Decimal::percent(50),
+        threshold: Decimal::percent(50),
+    };
+    contract.set_governance(&mut app, &owner, Some(config)).unwrap();
+
+    contract.donate(&mut app, &alice, &coins(30, ATOM)).unwrap();
+    contract.donate(&mut app, &bob, &coins(10, ATOM)).unwrap();
+    // Votes are weighted by the donations made before the block a proposal is created in
+    app.update_block(|block| block.height += 1);
+
+    let weight = contract.query_voting_weight(&app, &alice).unwrap();
+    assert_eq!(weight.weight, Uint128::new(30));
+    assert_eq!(weight.total, Uint128::new(40));
+
+    let err = contract.propose(&mut app, &owner, action.clone()).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NoVotingWeight));
+
+    contract.propose(&mut app, &alice, action.clone()).unwrap();
+    contract.vote(&mut app, &alice, 0, VoteOption::Yes).unwrap();
+
+    let err = contract.vote(&mut app, &alice, 0, VoteOption::No).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::AlreadyVoted { id: 0 }));
+
+    let end = app.block_info().time.plus_seconds(100);
+    let err = contract.execute_proposal(&mut app, &bob, 0).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::VotingNotEnded { end }));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+    let err = contract.vote(&mut app, &bob, 0, VoteOption::No).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::VotingClosed { id: 0 }));
+
+    let proposal = contract.query_proposal(&app, 0).unwrap();
+    assert_eq!(proposal.status, ProposalStatus::Passed);
+    assert_eq!(contract.query_votes(&app, 0).unwrap().votes, vec![
+        VoteResp { voter: alice.to_string(), option: VoteOption::Yes, weight: Uint128::new(30) },
+    ]);
+
+    // Anyone can execute a passed proposal, but only once
+    contract.execute_proposal(&mut app, &bob, 0).unwrap();
+    assert_eq!(contract.query_counter(&app, DEFAULT_COUNTER).unwrap().minimal_donation, Coin::new(20, ATOM));
+    assert_eq!(contract.query_proposal(&app, 0).unwrap().status, ProposalStatus::Executed);
+
+    let err = contract.execute_proposal(&mut app, &bob, 0).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::ProposalNotPassed { id: 0 }));
+
+    // Bob alone does not reach the quorum
+    contract.propose(&mut app, &bob, ProposalAction::UpdateOwner { owner: bob.to_string() }).unwrap();
+    contract.vote(&mut app, &bob, 1, VoteOption::Yes).unwrap();
+
+    // Donating after the proposal was created doesn't buy votes on it
+    contract.donate(&mut app, &carol, &coins(100, ATOM)).unwrap();
+    app.update_block(|block| block.height += 1);
+    let err = contract.vote(&mut app, &carol, 1, VoteOption::Yes).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NoVotingWeight));
+    assert_eq!(contract.query_proposal(&app, 1).unwrap().yes, Uint128::new(10));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(100));
+
+    assert_eq!(contract.query_proposal(&app, 1).unwrap().status, ProposalStatus::Rejected);
+    let err = contract.execute_proposal(&mut app, &bob, 1).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::ProposalNotPassed { id: 1 }));
+
+    // Proposals are only applied by the contract itself
+    let err = contract.execute(&mut app, &owner, &ExecMsg::ApplyProposal { action }, &[]).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: contract.addr().to_string() }));
+}
+
+#[test]
+fn sudo() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let rescue = Addr::unchecked("rescue");
+    let team = Addr::unchecked("team");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(50, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_label("Parent")
+        .call()
+        .unwrap();
+
+    let at = app.block_info().time.plus_seconds(100);
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_vesting(VestingSchedule::Cliff { at })
+        .call()
+        .unwrap();
+
+    contract.donate(&mut app, &sender, &coins(50, ATOM)).unwrap();
+    contract.create_counter(&mut app, &owner, "team", Coin::new(5, ATOM), Some(&team), None).unwrap();
+
+    contract.sudo(&mut app, &SudoMsg::SetOwner { owner: rescue.to_string() }).unwrap();
+    assert_eq!(contract.query_config(&app).unwrap().owner, rescue.to_string());
+    let err = contract.set_donor_mode(&mut app, &owner, DonorMode::Open {}).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: rescue.to_string() }));
+
+    // Counter owners are bypassed
+    contract.sudo(&mut app, &SudoMsg::ForceReset { counter: 7, counter_id: Some("team".to_owned()) }).unwrap();
+    assert_eq!(contract.query_counter(&app, "team").unwrap().value, 7);
+
+    // Vesting is bypassed
+    contract.sudo(&mut app, &SudoMsg::ForceWithdraw { to: rescue.to_string() }).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&rescue).unwrap(), coins(50, ATOM));
+    let err = contract.sudo(&mut app, &SudoMsg::ForceWithdraw { to: rescue.to_string() }).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NothingToWithdraw));
+
+    let new_parent = Parent {
+        addr: parent.addr().to_string(),
+        donating_period: 2,
+        part: Decimal::percent(10),
+        mode: ForwardMode::Period,
+        payout: ParentPayout::Push,
+    };
+    contract.sudo(&mut app, &SudoMsg::UpdateParent { parent: Some(new_parent.clone()) }).unwrap();
+    assert_eq!(contract.query_parent(&app).unwrap().parent, Some(new_parent));
+    assert_eq!(parent.query_children(&app, None, None).unwrap().children.len(), 1);
+
+    contract.sudo(&mut app, &SudoMsg::UpdateParent { parent: None }).unwrap();
+    assert_eq!(contract.query_parent(&app).unwrap().parent, None);
+}
+
+#[test]
+fn batch() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(50, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(5)
+        .call()
+        .unwrap();
+
+    contract.donate(&mut app, &sender, &coins(50, ATOM)).unwrap();
+
+    let msgs = vec![
+        AdminMsg::UpdateMinimalDonation { minimal_donation: Coin::new(20, ATOM) },
+        AdminMsg::SetDonorMode { mode: DonorMode::Allowlist {} },
+        AdminMsg::Withdraw { amount: None },
+    ];
+
+    let err = contract.batch(&mut app, &sender, msgs.clone()).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    let err = contract.batch(&mut app, &owner, vec![msgs[1].clone(); 11]).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::BatchTooLarge { max: 10 }));
+
+    let withdrawals = vec![AdminMsg::Withdraw { amount: Some(coins(30, ATOM)) }; 2];
+    let err = contract.batch(&mut app, &owner, withdrawals).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::BatchWithdrawals));
+
+    // Nothing is applied if any of the messages fails
+    let mut failing = msgs.clone();
+    failing.push(AdminMsg::AddMilestone { counter: 3, action: MilestoneAction::Event {} });
+    let err = contract.batch(&mut app, &owner, failing).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidMilestone { counter: 3 }));
+    assert_eq!(contract.query_config(&app).unwrap().minimal_donation, Coin::new(10, ATOM));
+
+    let resp = contract.batch(&mut app, &owner, msgs).unwrap();
+    assert!(resp.has_event(&Event::new("wasm-admin_msg")
+        .add_attribute("index", "0")
+        .add_attribute("action", "update_minimal_donation")));
+    assert!(resp.has_event(&Event::new("wasm-admin_msg")
+        .add_attribute("index", "2")
+        .add_attribute("action", "withdraw")));
+
+    let config = contract.query_config(&app).unwrap();
+    assert_eq!(config.minimal_donation, Coin::new(20, ATOM));
+    assert_eq!(config.donor_mode, DonorMode::Allowlist {});
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(50, ATOM));
+}
+
+#[test]
+fn scheduled_minimal_donation() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(0)
+        .call()
+        .unwrap();
+
+    let err = contract.update_minimal_donation(&mut app, &sender, Coin::new(5, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    contract.update_minimal_donation(&mut app, &owner, Coin::new(5, ATOM)).unwrap();
+    assert_eq!(contract.query_minimal_donation(&app).unwrap().current, Coin::new(5, ATOM));
+
+    let height = app.block_info().height;
+    let err = contract.schedule_minimal_donation(&mut app, &owner, Coin::new(20, ATOM), height).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidScheduleHeight { height }));
+
+    let scheduled = ScheduledMinimalDonation { minimal_donation: Coin::new(20, ATOM), height: height + 10 };
+    contract.schedule_minimal_donation(&mut app, &owner, Coin::new(20, ATOM), height + 10).unwrap();
+    assert_eq!(contract.query_minimal_donation(&app).unwrap().pending, Some(scheduled));
+
+    // The old minimum applies until the height
+    app.update_block(|block| block.height += 9);
+    contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+
+    app.update_block(|block| block.height += 1);
+    let resp = contract.query_minimal_donation(&app).unwrap();
+    assert_eq!(resp.current, Coin::new(20, ATOM));
+    assert_eq!(resp.pending, None);
+
+    let resp = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm-minimal_donation_update")
+        .add_attribute("minimal_donation", "20atom")
+        .add_attribute("height", (height + 10).to_string())));
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+
+    contract.donate(&mut app, &sender, &coins(20, ATOM)).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 2);
+    assert_eq!(contract.query_config(&app).unwrap().minimal_donation, Coin::new(20, ATOM));
+
+    // An immediate update drops the pending change
+    let height = app.block_info().height;
+    contract.schedule_minimal_donation(&mut app, &owner, Coin::new(30, ATOM), height + 10).unwrap();
+    contract.update_minimal_donation(&mut app, &owner, Coin::new(15, ATOM)).unwrap();
+    assert_eq!(contract.query_minimal_donation(&app).unwrap().pending, None);
+
+    app.update_block(|block| block.height += 10);
+    contract.donate(&mut app, &sender, &coins(15, ATOM)).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 3);
+    assert_eq!(contract.query_config(&app).unwrap().minimal_donation, Coin::new(15, ATOM));
+}
+
+#[test]
+fn oracle_minimal_donation() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, vec![coin(100, ATOM), coin(100, "osmo")]).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+    let oracle_id = OracleContract::store_code(&mut app);
+
+    let oracle = OracleContract::instantiate(&mut app, oracle_id, &owner).unwrap();
+    oracle.set_price(&mut app, &owner, ATOM, Decimal::from_ratio(10u128, 1u128)).unwrap();
+    oracle.set_price(&mut app, &owner, "osmo", Decimal::one()).unwrap();
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(0)
+        .call()
+        .unwrap();
+
+    let config = OracleConfig {
+        oracle: oracle.addr().to_string(),
+        minimum: Uint128::new(100),
+        denoms: vec![ATOM.to_owned(), "osmo".to_owned()],
+        max_age: 60,
+    };
+
+    let err = contract.set_oracle(&mut app, &sender, Some(config.clone())).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    let err = contract.set_oracle(&mut app, &owner, Some(OracleConfig { denoms: vec![], ..config.clone() })).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NoOracleDenoms));
+
+    let err = contract.set_oracle(&mut app, &owner, Some(OracleConfig { max_age: u64::MAX, ..config.clone() })).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidOracleMaxAge { max: 7 * 24 * 60 * 60 }));
+
+    contract.set_oracle(&mut app, &owner, Some(config.clone())).unwrap();
+    assert_eq!(contract.query_config(&app).unwrap().oracle, Some(config.clone()));
+
+    let resp = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("normalized_value", "100")));
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+
+    contract.donate(&mut app, &sender, &coins(50, "osmo")).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+
+    // Every coin has to meet the minimum on its own
+    let resp = contract.donate(&mut app, &sender, &[coin(5, ATOM), coin(50, "osmo")]).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("normalized_value", "100")));
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+
+    app.update_block(|block| block.time = block.time.plus_seconds(61));
+    let updated_at = app.block_info().time.minus_seconds(61);
+    let err = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::StalePrice { denom: ATOM.to_owned(), updated_at }));
+
+    oracle.set_price(&mut app, &owner, ATOM, Decimal::from_ratio(20u128, 1u128)).unwrap();
+    contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 2);
+
+    // Only what is left after the fee is valued
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(0)
+        .with_fee(FeeConfig { recipient: owner.to_string(), rate: Decimal::percent(20), mode: FeeMode::Accrued {} })
+        .call()
+        .unwrap();
+    contract.set_oracle(&mut app, &owner, Some(config)).unwrap();
+
+    let resp = contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("normalized_value", "80")));
+    assert_eq!(contract.query_value(&app).unwrap().value, 0);
+
+    oracle.set_price(&mut app, &owner, ATOM, Decimal::MAX).unwrap();
+    let err = contract.donate(&mut app, &sender, &coins(5, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::ValueOverflow { denom: ATOM.to_owned() }));
+}
+
+#[test]
+fn swap_to_treasury() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &owner, coins(1000, "usdc")).unwrap();
+        router.bank.init_balance(storage, &sender, vec![coin(100, ATOM), coin(100, "osmo")]).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+    let swap_id = SwapContract::store_code(&mut app);
+
+    let swap = SwapContract::instantiate(&mut app, swap_id, &owner).unwrap();
+    app.send_tokens(owner.clone(), swap.addr().clone(), &coins(1000, "usdc")).unwrap();
+    swap.set_rate(&mut app, &owner, ATOM, "usdc", Decimal::from_ratio(2u128, 1u128), Decimal::from_ratio(2u128, 1u128))
+        .unwrap();
+    // Pays out half of the quote, way over the slippage allowed
+    swap.set_rate(&mut app, &owner, "osmo", "usdc", Decimal::from_ratio(2u128, 1u128), Decimal::one()).unwrap();
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .call()
+        .unwrap();
+
+    let config = SwapConfig {
+        contract: swap.addr().to_string(),
+        treasury_denom: "usdc".to_owned(),
+        max_slippage: Decimal::percent(10),
+        trigger: SwapTrigger::Donate {},
+    };
+
+    let err = contract.set_swap(&mut app, &sender, Some(config.clone())).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: owner.to_string() }));
+
+    let err = contract.set_swap(&mut app, &owner, Some(SwapConfig { max_slippage: Decimal::percent(150), ..config.clone() }))
+        .unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidSlippage(Decimal::percent(150))));
+
+    contract.set_swap(&mut app, &owner, Some(config.clone())).unwrap();
+    assert_eq!(contract.query_config(&app).unwrap().swap, Some(config.clone()));
+
+    let resp = contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("swapped", "true")));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), coins(20, "usdc"));
+
+    // Failed swaps keep the donated coins
+    let resp = contract.donate(&mut app, &sender, &coins(10, "osmo")).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("swapped", "false")));
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), vec![coin(10, "osmo"), coin(20, "usdc")]);
+
+    // On withdrawal, treasury coins are sent as they are and failed swaps are refunded to the owner
+    contract.set_swap(&mut app, &owner, Some(SwapConfig { trigger: SwapTrigger::Withdraw {}, ..config })).unwrap();
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), vec![]);
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), vec![coin(10, "osmo"), coin(20, "usdc")]);
+
+    contract.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert_eq!(app.wrap().query_all_balances(contract.addr()).unwrap(), coins(10, ATOM));
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), vec![coin(10, "osmo"), coin(40, "usdc")]);
+}
+
+#[test]
+fn subscriptions() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let keeper = Addr::unchecked("keeper");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(0)
+        .call()
+        .unwrap();
+
+    let err = contract.subscribe(&mut app, &sender, &coins(10, ATOM), 0, &coins(25, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidSubscription));
+
+    let err = contract.subscribe(&mut app, &sender, &coins(10, ATOM), 100, &coins(5, ATOM)).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::InvalidSubscription));
+
+    let start = app.block_info().time;
+    contract.subscribe(&mut app, &sender, &coins(10, ATOM), 100, &coins(25, ATOM)).unwrap();
+    contract.subscribe(&mut app, &sender, &coins(10, ATOM), 50, &coins(20, ATOM)).unwrap();
+    assert_eq!(contract.query_subscriptions(&app, &sender).unwrap().subscriptions.len(), 2);
+
+    // Subscription funds are escrowed until their instalments are due
+    let err = contract.withdraw(&mut app, &owner).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NothingToWithdraw));
+
+    let resp = contract.process_subscriptions(&mut app, &keeper, None).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("dispatched", "2")));
+    assert!(resp.has_event(&Event::new("wasm-subscription_instalment").add_attribute("id", "0")));
+    assert_eq!(contract.query_value(&app).unwrap().value, 2);
+
+    let resp = contract.process_subscriptions(&mut app, &keeper, None).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("dispatched", "0")));
+    assert_eq!(contract.query_value(&app).unwrap().value, 2);
+
+    // The second subscription ends with its last instalment
+    app.update_block(|block| block.time = block.time.plus_seconds(50));
+    let resp = contract.process_subscriptions(&mut app, &keeper, None).unwrap();
+    assert!(resp.has_event(&Event::new("wasm-subscription_instalment").add_attribute("ended", "funds_used")));
+    assert_eq!(contract.query_value(&app).unwrap().value, 3);
+
+    let due = contract.query_subscriptions_by_due(&app, None).unwrap().subscriptions;
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].id, 0);
+    assert_eq!(due[0].next_due, start.plus_seconds(100));
+    assert_eq!(due[0].remaining, coins(15, ATOM));
+    assert_eq!(contract.query_subscriptions_by_due(&app, Some((due[0].next_due, 0))).unwrap().subscriptions, vec![]);
+
+    let err = contract.cancel_subscription(&mut app, &keeper, 0).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: sender.to_string() }));
+
+    contract.cancel_subscription(&mut app, &sender, 0).unwrap();
+    assert_eq!(contract.query_subscriptions(&app, &sender).unwrap().subscriptions, vec![]);
+    assert_eq!(app.wrap().query_all_balances(&sender).unwrap(), coins(70, ATOM));
+
+    contract.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(30, ATOM));
+}
+
+#[test]
+fn failing_subscription_instalment() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let keeper = Addr::unchecked("keeper");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, vec![coin(100, ATOM), coin(100, "osmo")]).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+    let oracle_id = OracleContract::store_code(&mut app);
+
+    let oracle = OracleContract::instantiate(&mut app, oracle_id, &owner).unwrap();
+    oracle.set_price(&mut app, &owner, "osmo", Decimal::one()).unwrap();
+
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(0)
+        .call()
+        .unwrap();
+
+    let config = OracleConfig {
+        oracle: oracle.addr().to_string(),
+        minimum: Uint128::new(100),
+        denoms: vec![ATOM.to_owned(), "osmo".to_owned()],
+        max_age: 60,
+    };
+    contract.set_oracle(&mut app, &owner, Some(config)).unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(61));
+    oracle.set_price(&mut app, &owner, ATOM, Decimal::from_ratio(10u128, 1u128)).unwrap();
+
+    contract.subscribe(&mut app, &sender, &coins(10, ATOM), 100, &coins(20, ATOM)).unwrap();
+    contract.subscribe(&mut app, &sender, &coins(10, "osmo"), 100, &coins(20, "osmo")).unwrap();
+
+    // The stale osmo price only ends its own subscription, the other instalment is still paid
+    let resp = contract.process_subscriptions(&mut app, &keeper, None).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("dispatched", "2")));
+    assert!(resp.has_event(&Event::new("wasm-subscription_instalment").add_attribute("id", "1").add_attribute("ended", "donation_failed")));
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+
+    let subscriptions = contract.query_subscriptions(&app, &sender).unwrap().subscriptions;
+    assert_eq!(subscriptions.len(), 1);
+    assert_eq!(subscriptions[0].id, 0);
+    assert_eq!(subscriptions[0].remaining, coins(10, ATOM));
+    assert_eq!(app.wrap().query_all_balances(&sender).unwrap(), vec![coin(80, ATOM), coin(100, "osmo")]);
+
+    let err = contract.execute(&mut app, &keeper, &ExecMsg::PayInstalment { id: 0 }, &[]).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::Unauthorized { owner: contract.addr().to_string() }));
+}
+
+#[test]
+fn pull_parent_payout() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+    let keeper = Addr::unchecked("keeper");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, coins(100, ATOM)).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent = CountingContract::builder(&mut app, code_id, &owner, Coin::new(5, ATOM))
+        .with_counter(0)
+        .call()
+        .unwrap();
+
+    let parent_config = Parent {
+        addr: parent.addr().to_string(),
+        donating_period: 1,
+        part: Decimal::percent(50),
+        mode: ForwardMode::Stream,
+        payout: ParentPayout::Pull,
+    };
+    let child = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(0)
+        .with_parent(parent_config)
+        .with_admin(&owner)
+        .call()
+        .unwrap();
+
+    let resp = child.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("accrued_to_parent", parent.addr().as_str())));
+    assert_eq!(parent.query_value(&app).unwrap().value, 0);
+    assert_eq!(child.query_parent_share(&app, parent.addr()).unwrap().accrued, coins(5, ATOM));
+
+    // The accrued share is not part of the donation balance
+    child.withdraw(&mut app, &owner).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&owner).unwrap(), coins(5, ATOM));
+
+    child.claim_parent_share(&mut app, &keeper, Some(parent.addr())).unwrap();
+    assert_eq!(parent.query_value(&app).unwrap().value, 1);
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(5, ATOM));
+    assert_eq!(child.query_parent_share(&app, parent.addr()).unwrap().accrued, vec![]);
+
+    let err = child.claim_parent_share(&mut app, parent.addr(), None).unwrap_err();
+    assert_eq!(err, TestError::Contract(ContractError::NothingToClaim));
+
+    // Existing parent configurations can be switched back on migration
+    CountingContract::migrate_with(&mut app, &owner, child.addr(), code_id, &MigrateMsg {
+        parent: None,
+        parent_payout: Some(ParentPayout::Push),
+    }).unwrap();
+    assert_eq!(child.query_parent(&app).unwrap().parent.unwrap().payout, ParentPayout::Push);
+
+    child.donate(&mut app, &sender, &coins(10, ATOM)).unwrap();
+    assert_eq!(child.query_parent_share(&app, parent.addr()).unwrap().accrued, vec![]);
+    assert_eq!(parent.query_value(&app).unwrap().value, 2);
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(10, ATOM));
+}
+
+#[test]
+fn donation_denom_and_dust_forwarding() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("sender");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank.init_balance(storage, &sender, vec![coin(100, ATOM), coin(100, "osmo")]).unwrap();
+    });
+    let code_id = CountingContract::store_code(&mut app);
+
+    let parent = CountingContract::builder(&mut app, code_id, &owner, Coin::new(0, ATOM))
+        .with_counter(0)
+        .call()
+        .unwrap();
+    let contract = CountingContract::builder(&mut app, code_id, &owner, Coin::new(10, ATOM))
+        .with_counter(0)
+        .with_parent(Parent {
+            addr: parent.addr().to_string(),
+            donating_period: 1,
+            part: Decimal::percent(10),
+            mode: ForwardMode::Period,
+            payout: ParentPayout::Push,
+        })
+        .call()
+        .unwrap();
+
+    // Denoms whose share rounds down to zero are not forwarded
+    contract.donate(&mut app, &sender, &[coin(20, ATOM), coin(5, "osmo")]).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+    assert_eq!(app.wrap().query_all_balances(parent.addr()).unwrap(), coins(2, ATOM));
+
+    // Only the minimal donation denom counts
+    contract.donate(&mut app, &sender, &coins(50, "osmo")).unwrap();
+    assert_eq!(contract.query_value(&app).unwrap().value, 1);
+}
@@ -1,12 +1,17 @@
-use cosmwasm_std::{Addr, Coin, Decimal};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, IbcEndpoint, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
 use serde::{Serialize, Deserialize};
+use crate::msg::{CampaignRelease, CampaignStatus, DonorMode, FeeMode, ForwardMode, GovernanceConfig, IbcParent,
+                 MilestoneAction, ParentPayout, OracleConfig, ProposalAction, SwapConfig, ScheduledMinimalDonation, VestingSchedule, VoteOption, VoucherSigner};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct State {
     pub counter: u64,
     pub minimal_donation: Coin,
     pub donating_parent: Option<u64>,
+    /// Owner of a counter created with `CreateCounter`, the default counter has none
+    #[serde(default)]
+    pub owner: Option<Addr>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -14,8 +19,215 @@ pub struct ParentDonation {
     pub address: Addr,
     pub donating_parent_period: u64,
     pub part: Decimal,
+    /// Missing for contracts configured before the stream mode was introduced
+    #[serde(default)]
+    pub mode: ForwardMode,
+    /// Missing for contracts configured before the pull payout was introduced
+    #[serde(default)]
+    pub payout: ParentPayout,
 }
 
-pub const PARENT_DONATION: Item<ParentDonation> = Item::new("parent_donation");
-pub const STATE: Item<State> = Item::new("state");
-pub const OWNER: Item<Addr> = Item::new("owner");
\ No newline at end of file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct ChildInfo {
+    pub received: Vec<Coin>,
+    /// Donations of approved children skip the donor checks
+    pub approved: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReceiptConfig {
+    Internal,
+    External { nft_contract: Addr },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Receipt {
+    pub owner: Addr,
+    pub counter: u64,
+    pub amount: Vec<Coin>,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Achievement {
+    pub donor: Addr,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Milestone {
+    pub action: MilestoneAction,
+    pub achieved: Option<Achievement>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchingPool {
+    pub id: u64,
+    pub sponsor: Addr,
+    pub remaining: Coin,
+    pub ratio: Decimal,
+    pub cap: Option<Uint128>,
+    pub expires_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Campaign {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub goal: Vec<Coin>,
+    pub raised: Vec<Coin>,
+    pub release: CampaignRelease,
+    pub status: CampaignStatus,
+    /// Funds refunded pro-rata to the contributions of a failed campaign
+    pub refund_pool: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Fee {
+    pub recipient: Addr,
+    pub rate: Decimal,
+    pub mode: FeeMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Proposal {
+    pub proposer: Addr,
+    pub action: ProposalAction,
+    pub end: Timestamp,
+    pub total_weight: Uint128,
+    /// Voting weights are taken at the start of this block
+    pub start_height: u64,
+    /// Governance settings when the proposal was created
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+    pub yes: Uint128,
+    pub no: Uint128,
+    pub executed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Ballot {
+    pub option: VoteOption,
+    pub weight: Uint128,
+}
+
+/// Channel to another counting contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IbcChannelInfo {
+    pub counterparty: IbcEndpoint,
+    pub received: Vec<Coin>,
+}
+
+/// Funds waiting for the parent to acknowledge the donation notice
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IbcForward {
+    pub transfer_channel_id: String,
+    pub address: String,
+    pub amount: Vec<Coin>,
+    pub timeout: u64,
+}
+
+/// Swap waiting for its reply. Withdrawn coins go to `recipient` if the swap fails.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PendingSwap {
+    pub offer: Coin,
+    pub recipient: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Subscription {
+    pub donor: Addr,
+    pub counter_id: String,
+    pub amount: Vec<Coin>,
+    pub interval: u64,
+    pub next_due: Timestamp,
+    pub remaining: Vec<Coin>,
+}
+
+/// Parent per counter id
+pub const PARENT_DONATION: Map<&str, ParentDonation> = Map::new("parent_donations");
+/// Counters by their ids
+pub const STATE: Map<&str, State> = Map::new("counters");
+/// Counter values by counter id and the height of the block they were set in
+pub const COUNTER_HISTORY: Map<(&str, u64), u64> = Map::new("counter_history");
+/// Number of blocks the counter history is kept for, everything is kept if not set
+pub const HISTORY_RETENTION: Item<u64> = Item::new("history_retention");
+/// Change of the default counter's minimal donation applied by the first donation at its height
+pub const SCHEDULED_MINIMAL_DONATION: Item<ScheduledMinimalDonation> = Item::new("scheduled_minimal_donation");
+/// Normalizes the minimal donation of the default counter when set
+pub const ORACLE: Item<OracleConfig> = Item::new("oracle");
+pub const SWAP: Item<SwapConfig> = Item::new("swap");
+/// Swaps by their reply ids
+pub const PENDING_SWAPS: Map<u64, PendingSwap> = Map::new("pending_swaps");
+pub const NEXT_SWAP_ID: Item<u64> = Item::new("next_swap_id");
+pub const OWNER: Item<Addr> = Item::new("owner");
+pub const MAX_PARENT_DEPTH: Item<u32> = Item::new("max_parent_depth");
+pub const CHILDREN: Map<&Addr, ChildInfo> = Map::new("children");
+pub const RECEIPT_CONFIG: Item<ReceiptConfig> = Item::new("receipt_config");
+pub const RECEIPTS: Map<u64, Receipt> = Map::new("receipts");
+pub const RECEIPTS_BY_OWNER: Map<(&Addr, u64), Empty> = Map::new("receipts_by_owner");
+pub const NEXT_RECEIPT_ID: Item<u64> = Item::new("next_receipt_id");
+pub const MILESTONES: Map<u64, Milestone> = Map::new("milestones");
+pub const MATCHING_POOL: Item<MatchingPool> = Item::new("matching_pool");
+pub const NEXT_MATCHING_POOL_ID: Item<u64> = Item::new("next_matching_pool_id");
+/// Amount matched per pool and donor
+pub const MATCHED: Map<(u64, &Addr), Uint128> = Map::new("matched");
+pub const CAMPAIGNS: Map<u64, Campaign> = Map::new("campaigns");
+pub const ACTIVE_CAMPAIGN: Item<u64> = Item::new("active_campaign");
+pub const NEXT_CAMPAIGN_ID: Item<u64> = Item::new("next_campaign_id");
+/// Funds donated per campaign and donor
+pub const CONTRIBUTIONS: Map<(u64, &Addr), Vec<Coin>> = Map::new("contributions");
+/// Funds of active campaigns and refunds not claimed yet
+pub const CAMPAIGN_ESCROW: Item<Vec<Coin>> = Item::new("campaign_escrow");
+pub const VESTING: Item<VestingSchedule> = Item::new("vesting");
+/// Total funds withdrawn by the owner
+pub const WITHDRAWN: Item<Vec<Coin>> = Item::new("withdrawn");
+pub const FEE: Item<Fee> = Item::new("fee");
+/// Total fees taken from donations
+pub const FEES_COLLECTED: Item<Vec<Coin>> = Item::new("fees_collected");
+/// Fees not claimed by the recipient yet
+pub const FEES_ACCRUED: Item<Vec<Coin>> = Item::new("fees_accrued");
+/// `DonorMode::Open` if not set
+pub const DONOR_MODE: Item<DonorMode> = Item::new("donor_mode");
+pub const ALLOWLIST: Map<&Addr, Empty> = Map::new("allowlist");
+pub const DENYLIST: Map<&Addr, Empty> = Map::new("denylist");
+pub const VOUCHER_SIGNER: Item<VoucherSigner> = Item::new("voucher_signer");
+/// Nonces of redeemed vouchers per donor
+pub const VOUCHER_NONCES: Map<(&Addr, u64), Empty> = Map::new("voucher_nonces");
+/// Funds credited to donors by vouchers
+pub const CREDITS: Map<&Addr, Vec<Coin>> = Map::new("credits");
+pub const GOVERNANCE: Item<GovernanceConfig> = Item::new("governance");
+/// Amount directly donated per donor in the governance denom
+pub const VOTING_WEIGHTS: Map<&Addr, Uint128> = Map::new("voting_weights");
+pub const TOTAL_WEIGHT: Item<Uint128> = Item::new("total_weight");
+/// Voting weights by donor and the height of the block they were changed in
+pub const VOTING_WEIGHT_HISTORY: Map<(&Addr, u64), Uint128> = Map::new("voting_weight_history");
+/// Total voting weight by the height of the block it was changed in
+pub const TOTAL_WEIGHT_HISTORY: Map<u64, Uint128> = Map::new("total_weight_history");
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+pub const NEXT_PROPOSAL_ID: Item<u64> = Item::new("next_proposal_id");
+pub const BALLOTS: Map<(u64, &Addr), Ballot> = Map::new("ballots");
+pub const IBC_PARENT: Item<IbcParent> = Item::new("ibc_parent");
+/// Counterparties channels can be opened with, by connection id and port id
+pub const IBC_ALLOWLIST: Map<(&str, &str), Empty> = Map::new("ibc_allowlist");
+/// Connected channels to other counting contracts
+pub const IBC_CHANNELS: Map<&str, IbcChannelInfo> = Map::new("ibc_channels");
+pub const IBC_FORWARDS: Map<u64, IbcForward> = Map::new("ibc_forwards");
+pub const NEXT_IBC_FORWARD_ID: Item<u64> = Item::new("next_ibc_forward_id");
+/// Funds of all pending forwards
+pub const IBC_ESCROW: Item<Vec<Coin>> = Item::new("ibc_escrow");
+/// Shares accrued for parents in the pull payout mode
+pub const PARENT_SHARES: Map<&Addr, Vec<Coin>> = Map::new("parent_shares");
+/// Sum of all accrued parent shares
+pub const PARENT_SHARE_ESCROW: Item<Vec<Coin>> = Item::new("parent_share_escrow");
+pub const SUBSCRIPTIONS: Map<u64, Subscription> = Map::new("subscriptions");
+pub const SUBSCRIPTIONS_BY_DONOR: Map<(&Addr, u64), Empty> = Map::new("subscriptions_by_donor");
+/// Subscriptions by the time their next instalment is due in nanoseconds, and their id
+pub const SUBSCRIPTIONS_BY_DUE: Map<(u64, u64), Empty> = Map::new("subscriptions_by_due");
+pub const NEXT_SUBSCRIPTION_ID: Item<u64> = Item::new("next_subscription_id");
+/// Funds left in all subscriptions
+pub const SUBSCRIPTION_ESCROW: Item<Vec<Coin>> = Item::new("subscription_escrow");
+
+pub const DEFAULT_MAX_PARENT_DEPTH: u32 = 10;
+/// Id of the counter created on instantiation, used when no counter id is given
+pub const DEFAULT_COUNTER: &str = "default";
\ No newline at end of file
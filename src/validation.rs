@@ -0,0 +1,190 @@
+use cosmwasm_std::{Addr, Coin, Decimal, Deps, Env};
+use crate::contract::query_parent;
+use crate::error::ContractError;
+use crate::msg::{FeeConfig, GovernanceConfig, IbcParent, MilestoneAction, OracleConfig, Parent, ProposalAction, SwapConfig,
+                 VestingSchedule};
+use crate::state::{Fee, IBC_CHANNELS, ParentDonation};
+
+/// Oracle prices older than a week are never accepted
+const MAX_ORACLE_AGE: u64 = 7 * 24 * 60 * 60;
+
+/// Checks the denom against the Cosmos SDK format: `[a-zA-Z][a-zA-Z0-9/:._-]{2,127}`.
+pub fn denom(denom: &str) -> Result<(), ContractError> {
+    let mut chars = denom.chars();
+
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && (3..=128).contains(&denom.len())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+
+    if !valid {
+        return Err(ContractError::InvalidDenom(denom.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Counter ids are 1 to 64 characters long, using only ASCII alphanumerics, `-` and `_`.
+pub fn counter_id(id: &str) -> Result<(), ContractError> {
+    let valid = (1..=64).contains(&id.len())
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !valid {
+        return Err(ContractError::InvalidCounterId(id.to_owned()));
+    }
+
+    Ok(())
+}
+
+pub fn minimal_donation(minimal_donation: &Coin) -> Result<(), ContractError> {
+    denom(&minimal_donation.denom)
+}
+
+pub fn vesting(schedule: &VestingSchedule) -> Result<(), ContractError> {
+    match schedule {
+        VestingSchedule::Linear { start, end } if end <= start => Err(ContractError::InvalidVesting),
+        _ => Ok(()),
+    }
+}
+
+pub fn fee(deps: Deps, fee: FeeConfig) -> Result<Fee, ContractError> {
+    if fee.rate >= Decimal::one() {
+        return Err(ContractError::InvalidFeeRate(fee.rate));
+    }
+
+    Ok(Fee {
+        recipient: deps.api.addr_validate(&fee.recipient)?,
+        rate: fee.rate,
+        mode: fee.mode,
+    })
+}
+
+pub fn governance(config: &GovernanceConfig) -> Result<(), ContractError> {
+    if config.quorum.is_zero() || config.quorum > Decimal::one() || config.threshold >= Decimal::one() {
+        return Err(ContractError::InvalidGovernanceConfig);
+    }
+
+    Ok(())
+}
+
+/// Checks what can be checked upfront, the parent chain is only validated when the proposal is applied.
+pub fn proposal_action(deps: Deps, action: &ProposalAction) -> Result<(), ContractError> {
+    match action {
+        ProposalAction::UpdateMinimalDonation { minimal_donation: donation } => minimal_donation(donation),
+        ProposalAction::UpdateParent { parent: Some(parent) } => {
+            deps.api.addr_validate(&parent.addr)?;
+            Ok(())
+        }
+        ProposalAction::UpdateParent { parent: None } => Ok(()),
+        ProposalAction::UpdateOwner { owner } => {
+            deps.api.addr_validate(owner)?;
+            Ok(())
+        }
+    }
+}
+
+pub fn milestone_action(deps: Deps, action: &MilestoneAction) -> Result<(), ContractError> {
+    match action {
+        MilestoneAction::Bonus { amount } => amount.iter().try_for_each(|coin| denom(&coin.denom)),
+        MilestoneAction::Event {} => Ok(()),
+        MilestoneAction::Notify { contract, .. } => {
+            deps.api.addr_validate(contract)?;
+            Ok(())
+        }
+    }
+}
+
+/// Validates the parent configuration and turns it into what is kept in the storage.
+pub fn parent(deps: Deps, env: &Env, parent: Parent, max_depth: u32) -> Result<ParentDonation, ContractError> {
+    let address = deps.api.addr_validate(&parent.addr)?;
+
+    if address == env.contract.address {
+        return Err(ContractError::SelfParent);
+    }
+
+    if parent.donating_period == 0 {
+        return Err(ContractError::InvalidPeriod);
+    }
+
+    if parent.part > Decimal::one() {
+        return Err(ContractError::InvalidPart(parent.part));
+    }
+
+    parent_chain(deps, env, &address, max_depth)?;
+
+    Ok(ParentDonation {
+        address,
+        donating_parent_period: parent.donating_period,
+        part: parent.part,
+        mode: parent.mode,
+        payout: parent.payout,
+    })
+}
+
+pub fn oracle(deps: Deps, config: &OracleConfig) -> Result<(), ContractError> {
+    deps.api.addr_validate(&config.oracle)?;
+
+    if config.denoms.is_empty() {
+        return Err(ContractError::NoOracleDenoms);
+    }
+
+    if config.max_age > MAX_ORACLE_AGE {
+        return Err(ContractError::InvalidOracleMaxAge { max: MAX_ORACLE_AGE });
+    }
+
+    for listed in &config.denoms {
+        denom(listed)?;
+    }
+
+    Ok(())
+}
+
+pub fn swap(deps: Deps, config: &SwapConfig) -> Result<(), ContractError> {
+    deps.api.addr_validate(&config.contract)?;
+    denom(&config.treasury_denom)?;
+
+    if config.max_slippage > Decimal::one() {
+        return Err(ContractError::InvalidSlippage(config.max_slippage));
+    }
+
+    Ok(())
+}
+
+/// The remote address can't be validated on this chain, the parent channel has to be connected.
+pub fn ibc_parent(deps: Deps, parent: &IbcParent) -> Result<(), ContractError> {
+    if parent.part > Decimal::one() {
+        return Err(ContractError::InvalidPart(parent.part));
+    }
+
+    if parent.timeout == 0 {
+        return Err(ContractError::InvalidIbcTimeout);
+    }
+
+    if !IBC_CHANNELS.has(deps.storage, &parent.channel_id) {
+        return Err(ContractError::UnknownChannel { channel_id: parent.channel_id.clone() });
+    }
+
+    Ok(())
+}
+
+/// Walks up the parent chain starting at `parent`, failing if it comes back to this contract
+/// or gets longer than `max_depth`. Legacy counting contracts end the chain.
+pub fn parent_chain(deps: Deps, env: &Env, parent: &Addr, max_depth: u32) -> Result<(), ContractError> {
+    let mut current = Some(parent.clone());
+    let mut depth = 0;
+
+    while let Some(addr) = current {
+        depth += 1;
+
+        if addr == env.contract.address {
+            return Err(ContractError::ParentCycle);
+        }
+
+        if depth > max_depth {
+            return Err(ContractError::ParentDepthExceeded { max_depth });
+        }
+
+        current = query_parent(deps, &addr)?;
+    }
+
+    Ok(())
+}